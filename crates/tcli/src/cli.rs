@@ -1,19 +1,55 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, Subcommand, ValueEnum};
 
-use crate::ts::package_reference::PackageReference;
-use crate::ts::version::Version;
-use crate::util::os::OS;
+use tcli::project::{BuildEmit, DEFAULT_MANIFEST};
+use tcli::ts::package_reference::{LoosePackageReference, PackageReference};
+use tcli::ts::v1::ecosystem::SchemaChannel;
+use tcli::ts::v1::models::ecosystem::GameDefPlatform;
+use tcli::ts::version::Version;
+use tcli::util::os::OS;
+use tcli::util::SecretString;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     #[clap(subcommand)]
     pub commands: Commands,
+
+    /// The format of the command's output. `jsonl` streams one JSON object per event, each
+    /// with a `type` field, instead of human readable progress.
+    #[clap(long, global = true, value_enum, default_value = "human")]
+    pub output: OutputFormat,
+
+    /// Print the command's result to stdout as a single JSON document, without progress bars or
+    /// colors. Shorthand for `--output json`.
+    #[clap(
+        long,
+        global = true,
+        default_value = "false",
+        conflicts_with = "output"
+    )]
+    pub json: bool,
+
+    /// Print errors to stderr as a JSON object with a stable `error_code`, the message and any
+    /// structured fields. Implied by `--output json` and `--output jsonl`.
+    #[clap(long, global = true, default_value = "false")]
+    pub json_errors: bool,
+
+    /// Log the method, url, status and duration of every network request to stderr.
+    #[clap(long, global = true, default_value = "false")]
+    pub trace_network: bool,
 }
 
-const DEFAULT_MANIFEST: &str = "./Thunderstore.toml";
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    /// The command's result as a single JSON document.
+    Json,
+    /// One JSON object per event, as the command progresses.
+    Jsonl,
+}
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum InitSubcommand {
@@ -35,6 +71,27 @@ pub enum InitSubcommand {
     Profile,
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum GamesSubcommand {
+    /// Change the executable used to launch an imported game.
+    SetExe {
+        /// The identifier of the imported game.
+        game_id: String,
+
+        /// Path to the game executable.
+        #[clap(required_unless_present = "auto", conflicts_with = "auto")]
+        exe_path: Option<PathBuf>,
+
+        /// Detect the executable within the game directory, as when the game was imported.
+        #[clap(long, default_value = "false")]
+        auto: bool,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum ListSubcommand {
     /// List the platforms tcli supports.
@@ -62,8 +119,174 @@ pub enum ListSubcommand {
     },
 
     InstalledMods {
+        /// Group installed packages under their namespace, flagging package names which are
+        /// shared across multiple namespaces.
+        #[clap(long, default_value = "false")]
+        by_namespace: bool,
+
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProjectsSubcommand {
+    /// List every project tcli has operated on, most recently used first.
+    List {
+        /// Print the projects as JSON.
+        #[clap(long, default_value = "false")]
+        json: bool,
+    },
+
+    /// Stop tracking a project, for example one which has been moved or deleted.
+    Forget {
+        /// The project directory to forget.
+        #[clap(required_unless_present = "stale")]
+        path: Option<PathBuf>,
+
+        /// Forget every project which no longer exists.
+        #[clap(long, default_value = "false", conflicts_with = "path")]
+        stale: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum PackageSubcommand {
+    /// Print metadata about a package within the repository without installing it.
+    Info {
+        /// Package name in the format 'namespace-name(-version)'. Defaults to the latest version.
+        package: LoosePackageReference,
+
+        /// Print the metadata as JSON.
+        #[clap(long, default_value = "false")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheSubcommand {
+    /// Check every cached package for incomplete extractions and interrupted downloads,
+    /// removing them so they're downloaded again when next needed.
+    Verify {
+        /// The package cache to verify. Defaults to the package cache within the tcli home
+        /// directory.
+        #[clap(long)]
+        cache_dir: Option<PathBuf>,
+    },
+
+    /// Remove cached packages which aren't installed within the project, or with `--orphans`,
+    /// within any project tcli has operated on.
+    Clean {
+        /// Only remove packages which aren't installed within any project tcli has operated on,
+        /// rather than just the given project.
+        #[clap(long, default_value = "false")]
+        orphans: bool,
+
+        /// Skip the confirmation prompt.
+        #[clap(long, short, default_value = "false")]
+        yes: bool,
+
+        /// The package cache to clean. Defaults to the package cache within the tcli home
+        /// directory.
+        #[clap(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Path of the project configuration file.
         #[clap(long, default_value = DEFAULT_MANIFEST)]
         project_path: PathBuf,
+    },
+
+    /// Print the directory a package is cached in. Fails if the package isn't cached.
+    Path {
+        /// Package name in the format 'namespace-name-version'.
+        package: PackageReference,
+
+        /// Print the path as JSON.
+        #[clap(long, default_value = "false")]
+        json: bool,
+
+        /// The package cache to look within. Defaults to the package cache within the tcli home
+        /// directory.
+        #[clap(long)]
+        cache_dir: Option<PathBuf>,
+    },
+}
+
+/// The forms a package can be emitted in, see [`BuildEmit`].
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum BuildEmitArg {
+    /// A zip archive, ready to be published.
+    Zip,
+    /// A loose directory containing exactly what would be placed in the archive.
+    Dir,
+    /// Both a zip archive and a loose directory.
+    Both,
+}
+
+impl From<BuildEmitArg> for BuildEmit {
+    fn from(value: BuildEmitArg) -> Self {
+        match value {
+            BuildEmitArg::Zip => BuildEmit::Zip,
+            BuildEmitArg::Dir => BuildEmit::Dir,
+            BuildEmitArg::Both => BuildEmit::Both,
+        }
+    }
+}
+
+/// The release channel the ecosystem schema is downloaded from, see [`SchemaChannel`].
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum SchemaChannelArg {
+    /// The schema version this release of tcli was built against.
+    Stable,
+    /// The newest schema published by Thunderstore, which may contain breaking changes.
+    DevLatest,
+}
+
+impl From<SchemaChannelArg> for SchemaChannel {
+    fn from(value: SchemaChannelArg) -> Self {
+        match value {
+            SchemaChannelArg::Stable => SchemaChannel::Stable,
+            SchemaChannelArg::DevLatest => SchemaChannel::DevLatest,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigSubcommand {
+    /// Persist a value into the user-wide tcli configuration.
+    Set {
+        /// The configuration key to set.
+        key: ConfigKey,
+
+        /// The value to set the key to.
+        value: String,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ConfigKey {
+    /// Path to the Steam installation directory used when importing games.
+    SteamDir,
+    /// The release channel the ecosystem schema is downloaded from.
+    SchemaChannel,
+    /// Whether to refuse installing packages flagged as containing NSFW content.
+    BlockNsfw,
+}
+
+impl ConfigKey {
+    /// The name of the key within the configuration file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigKey::SteamDir => "steam_dir",
+            ConfigKey::SchemaChannel => "schema_channel",
+            ConfigKey::BlockNsfw => "block_nsfw",
+        }
     }
 }
 
@@ -100,6 +323,16 @@ pub enum Commands {
         #[clap(long)]
         output_dir: Option<PathBuf>,
 
+        /// Produce a byte-identical archive from identical inputs by giving every entry a fixed
+        /// modification time, taken from SOURCE_DATE_EPOCH if set.
+        #[clap(long, default_value = "false")]
+        reproducible: bool,
+
+        /// The form to emit the package in. A directory is written alongside the archive,
+        /// named after the package.
+        #[clap(long, value_enum, default_value = "zip")]
+        emit: BuildEmitArg,
+
         /// Path for the project configuration file.
         #[clap(long, default_value = DEFAULT_MANIFEST)]
         project_path: PathBuf,
@@ -113,7 +346,7 @@ pub enum Commands {
 
         /// Authentication token to use when publishing the package.
         #[clap(long)]
-        token: Option<String>,
+        token: Option<SecretString>,
 
         /// Name for the package.
         #[clap(long)]
@@ -141,8 +374,73 @@ pub enum Commands {
         /// Path to a package .zip or package name in the format 'namespace-name(-version)'.
         packages: Vec<PackageReference>,
 
+        /// Directory where downloaded packages are cached. Defaults to the package cache within
+        /// the tcli home directory.
+        #[clap(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Discard the existing lockfile, for example when it is corrupt, and write a new one
+        /// containing only the packages being added. Previously installed packages are no longer
+        /// tracked until they're added again.
+        #[clap(long, default_value = "false")]
+        ignore_locked: bool,
+
+        /// Print a summary of the disk and network usage of the installation.
+        #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "text")]
+        report: Option<ReportFormat>,
+
+        /// Annotate each resolved package with where it was resolved from, what required it and
+        /// the versions which were considered.
+        #[clap(long, default_value = "false")]
+        explain: bool,
+
+        /// Fail instead of warning when a package being installed is deprecated.
+        #[clap(long, default_value = "false")]
+        no_deprecated: bool,
+
+        /// Fail instead of warning when a package being installed is flagged as containing NSFW
+        /// content. Enabled by default with the `block_nsfw` config value.
+        #[clap(long, default_value = "false")]
+        block_nsfw: bool,
+
+        /// Show what would be installed, upgraded and removed without changing the project.
+        #[clap(long, default_value = "false")]
+        dry_run: bool,
+
+        /// Don't draw progress bars, only printing a line to stderr as each package is installed.
+        #[clap(long, short, default_value = "false")]
+        quiet: bool,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+
+    /// Removes every installed mod from the project, keeping the project configuration and
+    /// imported games.
+    UninstallAll {
+        /// Skip the confirmation prompt.
+        #[clap(long, short, default_value = "false")]
+        yes: bool,
+
         /// Path of the project configuration file.
-        #[clap(long, default_value = "./")]
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+
+    /// Removes installed files which don't belong to any package in the lockfile, such as those
+    /// left behind by an interrupted install.
+    Clean {
+        /// List what would be removed without removing anything.
+        #[clap(long, default_value = "false")]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt.
+        #[clap(long, short, default_value = "false")]
+        yes: bool,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
         project_path: PathBuf,
     },
 
@@ -172,12 +470,90 @@ pub enum Commands {
         project_path: PathBuf,
     },
 
+    /// Merge the dependencies of several projects into a new project configuration. When
+    /// projects depend on the same package the highest version wins.
+    Merge {
+        /// The project configuration files to merge. All other settings are taken from the first.
+        #[clap(num_args = 2.., required = true)]
+        projects: Vec<PathBuf>,
+
+        /// Path to write the merged project configuration to.
+        #[clap(long, short)]
+        output_path: PathBuf,
+    },
+
+    /// Check the project's dependencies for versions pinned by the project which are overridden
+    /// by other packages, exiting with an error if any are found.
+    Audit {
+        /// Print the conflicts as a JSON array.
+        #[clap(long, default_value = "false")]
+        json: bool,
+
+        /// Directory where downloaded packages are cached. Defaults to the package cache within
+        /// the tcli home directory.
+        #[clap(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+
+    /// List installed packages which have a newer version available within the repository.
+    Outdated {
+        /// Include packages installed as dependencies of other packages, rather than only the
+        /// project's direct dependencies.
+        #[clap(long, default_value = "false")]
+        all: bool,
+
+        /// Print every checked package as a JSON array.
+        #[clap(long, default_value = "false")]
+        json: bool,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+
+    /// Print the resolved list of packages required by the project's dependencies without
+    /// installing anything.
+    Resolve {
+        /// Print the resolved packages as a JSON array.
+        #[clap(long, default_value = "false")]
+        json: bool,
+
+        /// Annotate each resolved package with where it was resolved from, what required it and
+        /// the versions which were considered.
+        #[clap(long, default_value = "false")]
+        explain: bool,
+
+        /// Directory where downloaded packages are cached. Defaults to the package cache within
+        /// the tcli home directory.
+        #[clap(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+
     /// Imports a new game for use by tcli.
     ImportGame {
-        /// The identifier of the game to import.
+        /// The identifier of the game to import. Games whose identifier or name contain the given
+        /// text are matched when no game has the exact identifier.
         ///
         /// Use the `list` command to query the list of imported and supported games.
-        game_id: String,
+        #[clap(required_unless_present = "from_file")]
+        game_id: Option<String>,
+
+        /// Import every game within a newline delimited list file instead. Each line contains a
+        /// game identifier, optionally followed by the platform to search first, for example
+        /// "riskofrain2 steam". Lines starting with '#' are ignored.
+        #[clap(
+            long,
+            conflicts_with_all = ["game_id", "custom_id", "custom_name", "exe_path"]
+        )]
+        from_file: Option<PathBuf>,
 
         #[clap(long)]
         /// The custom identifier this game will be referenced by.
@@ -187,10 +563,44 @@ pub enum Commands {
         /// The custom name this game will be displayed as.
         custom_name: Option<String>,
 
-        /// Path to the game executable to use when launching the game. Only works with servers.
-        #[clap(long)]
+        /// Path to the game executable to use when launching the game, for games installed with a
+        /// non-standard executable. Defaults to the first of the game's known executables found
+        /// within the game directory.
+        #[clap(long, visible_alias = "exe")]
         exe_path: Option<PathBuf>,
 
+        /// Path to the Steam installation to search for the game. Defaults to the `steam-dir`
+        /// config value if set, otherwise the Steam installation is located automatically.
+        #[clap(long)]
+        steam_dir: Option<PathBuf>,
+
+        /// Persist the given --steam-dir as the default for future imports.
+        #[clap(long, default_value = "false", requires = "steam_dir")]
+        save_steam_dir: bool,
+
+        /// Comma separated list of platforms to search for the game first, for example
+        /// "steam,egs". Other platforms are searched afterwards in the schema's order.
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_parser = PossibleValuesParser::new(GameDefPlatform::NAMES)
+        )]
+        platform_priority: Vec<String>,
+
+        /// When the game identifier matches several games, import the Nth match (starting at 1)
+        /// instead of asking which one to import.
+        #[clap(long, conflicts_with = "first")]
+        select: Option<usize>,
+
+        /// When the game identifier matches several games, import the first match instead of
+        /// asking which one to import.
+        #[clap(long, default_value = "false")]
+        first: bool,
+
+        /// Overwrite an existing registry entry for the game without asking for confirmation.
+        #[clap(long, short, default_value = "false")]
+        yes: bool,
+
         /// Directory where tcli keeps its data.
         /// %APPDATA%/Roaming/tcli on Windows, ~/.config/tcli on Linux.
         #[clap(long)]
@@ -205,6 +615,18 @@ pub enum Commands {
         project_path: PathBuf,
     },
 
+    /// Removes an imported game from the project. Installed mods are left untouched.
+    RemoveGame {
+        /// The identifier of the imported game.
+        ///
+        /// Use the `list imported-games` command to query the list of imported games.
+        game_id: String,
+
+        /// Path of the project configuration file.
+        #[clap(long, default_value = DEFAULT_MANIFEST)]
+        project_path: PathBuf,
+    },
+
     /// Run a game with mods.
     Run {
         /// The identifier of the game to run.
@@ -240,6 +662,69 @@ pub enum Commands {
         command: ListSubcommand,
     },
 
+    /// Manage the games imported into a project.
+    Games {
+        #[clap(subcommand)]
+        command: GamesSubcommand,
+    },
+
     /// Update the tcli ecosystem schema.
-    UpdateSchema,
+    UpdateSchema {
+        /// The release channel to download the schema from. Defaults to the `schema-channel`
+        /// config value if set, otherwise the stable channel.
+        #[clap(long, value_enum)]
+        schema_channel: Option<SchemaChannelArg>,
+    },
+
+    /// Manage the projects tcli has operated on.
+    Projects {
+        #[clap(subcommand)]
+        command: ProjectsSubcommand,
+    },
+
+    /// Inspect packages within the repository.
+    Package {
+        #[clap(subcommand)]
+        command: PackageSubcommand,
+    },
+
+    /// Manage the local package cache.
+    Cache {
+        #[clap(subcommand)]
+        command: CacheSubcommand,
+    },
+
+    /// Manage the user-wide tcli configuration.
+    Config {
+        #[clap(subcommand)]
+        command: ConfigSubcommand,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn merge_output_path_does_not_clash_with_global_output() {
+        // Clap only reports arguments clashing with a global argument by panicking on parse.
+        let args = Args::try_parse_from([
+            "tcli",
+            "--json",
+            "merge",
+            "a.toml",
+            "b.toml",
+            "--output-path",
+            "merged.toml",
+        ])
+        .unwrap();
+
+        assert!(args.json);
+        assert!(matches!(
+            args.commands,
+            Commands::Merge { output_path, .. } if output_path == Path::new("merged.toml")
+        ));
+    }
 }
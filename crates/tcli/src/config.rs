@@ -1,15 +1,22 @@
 use std::env::{self, VarError};
+use std::fs;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
 use figment::providers::{Env, Format, Serialized, Toml};
 use figment::Figment;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, IoResultToTcli};
+use crate::ts::v1::ecosystem::SchemaChannel;
+use crate::util::file;
 use crate::TCLI_HOME;
 
 pub enum Vars {
     HomeDir,
     AuthKey,
+    SourceDateEpoch,
+    NetworkRetries,
 }
 
 impl Vars {
@@ -21,6 +28,8 @@ impl Vars {
         match self {
             Vars::HomeDir => "TCLI_HOME",
             Vars::AuthKey => "TCLI_AUTH_KEY",
+            Vars::SourceDateEpoch => "SOURCE_DATE_EPOCH",
+            Vars::NetworkRetries => "TCLI_NETWORK_RETRIES",
         }
     }
 }
@@ -28,25 +37,49 @@ impl Vars {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub package_cache: PathBuf,
+    pub steam_dir: Option<PathBuf>,
+    pub schema_channel: SchemaChannel,
+    /// Refuse to install packages flagged as containing NSFW content.
+    pub block_nsfw: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             package_cache: TCLI_HOME.join("package_cache"),
+            steam_dir: None,
+            schema_channel: SchemaChannel::default(),
+            block_nsfw: false,
         }
     }
 }
 
 impl Config {
-    pub fn load(project_dir: &Path) -> Result<Self, figment::Error> {
-        dbg!(project_dir.join("Config.toml"));
-
+    pub fn load(project_dir: &Path) -> Result<Self, Error> {
         Figment::new()
             .merge(Toml::file(TCLI_HOME.join("Config.toml")))
             .merge(Toml::file(project_dir.join("Config.toml")))
             .merge(Env::prefixed("TCLI_"))
             .join(Serialized::defaults(Config::default()))
             .extract()
+            .map_err(|e| Error::InvalidConfig(Box::new(e)))
+    }
+
+    /// Persist a single value into the user config file within TCLI_HOME, preserving any other
+    /// values already set within it.
+    pub fn set_user_value(key: &str, value: impl Into<toml::Value>) -> Result<(), Error> {
+        let path = TCLI_HOME.join("Config.toml");
+
+        let mut table = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str::<toml::Table>(&contents)
+                .map_err(|e| Error::InvalidConfig(Box::new(e.to_string().into())))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => toml::Table::new(),
+            Err(e) => return Err(Error::FileIoError(path, e)),
+        };
+
+        table.insert(key.to_string(), value.into());
+
+        fs::create_dir_all(TCLI_HOME.as_path()).map_fs_error(TCLI_HOME.as_path())?;
+        file::write_atomic(&path, toml::to_string_pretty(&table).unwrap())
     }
 }
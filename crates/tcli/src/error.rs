@@ -1,7 +1,12 @@
 use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
+use itertools::Itertools;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
 
+/// Discriminants are exposed as stable error codes through `--json-errors`, so new variants must
+/// only ever be appended.
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, thiserror::Error)]
 #[repr(u32)]
@@ -53,6 +58,163 @@ pub enum Error {
 
     #[error("An error occurred while parsing JSON: {0}")]
     JsonParserError(#[from] serde_json::Error),
+
+    #[error(
+        "The lockfile at {0} could not be read: {1}. Use `tcli add --ignore-locked` to replace it."
+    )]
+    InvalidLockFile(PathBuf, serde_json::Error),
+
+    #[error("Failed to load configuration: {0}")]
+    InvalidConfig(Box<figment::Error>),
+
+    #[error("Could not locate a Steam installation.")]
+    SteamDirNotFound,
+
+    #[error(
+        "Steam app {app_id} is not installed within any of the searched libraries: {}.",
+        .libraries.iter().map(|x| x.display()).join(", ")
+    )]
+    SteamAppNotFound {
+        app_id: u32,
+        libraries: Vec<PathBuf>,
+    },
+
+    #[error("Could not find an installation of '{0}' on any supported platform.")]
+    GameNotFound(String),
+
+    #[error("The game '{0}' has not been imported into this project.")]
+    GameNotImported(String),
+
+    #[error("Could not find any of the known executables for the game within {0}.")]
+    ExeNotFound(PathBuf),
+
+    #[error("The file at {0} does not exist.")]
+    FileNotFound(PathBuf),
+
+    #[error("The readme at {0} is empty. Thunderstore rejects packages without a readme.")]
+    EmptyReadme(PathBuf),
+
+    #[error("This command requires a {expected} project, but the project is a {found}.")]
+    WrongProjectKind {
+        expected: crate::project::ProjectKind,
+        found: crate::project::ProjectKind,
+    },
+
+    #[error("The audit found {0} dependency conflicts.")]
+    AuditFailed(usize),
+
+    #[error("The package {0} is not in the package cache.")]
+    PackageNotCached(String),
+
+    #[error("SOURCE_DATE_EPOCH '{0}' is not a valid timestamp between 1980 and 2107.")]
+    InvalidSourceDateEpoch(String),
+
+    #[error("Line {line} of the import list is invalid: {reason}.")]
+    InvalidImportListLine { line: usize, reason: String },
+
+    #[error("{0} games could not be imported.")]
+    ImportFailed(usize),
+
+    #[error("Refusing to install deprecated packages: {}.", .0.join(", "))]
+    DeprecatedPackages(Vec<String>),
+
+    #[error("Refusing to install packages flagged as NSFW: {}.", .0.join(", "))]
+    NsfwPackages(Vec<String>),
+
+    #[error("The ecosystem schema doesn't describe the mod loader setup of '{0}'.")]
+    MissingR2ModManDefinition(String),
+
+    #[error(
+        "'{query}' matches multiple games, choose one with --select or --first: {}.",
+        .candidates.iter().enumerate().map(|(i, x)| format!("{}. {x}", i + 1)).join(", ")
+    )]
+    AmbiguousGameId {
+        query: String,
+        candidates: Vec<String>,
+    },
+
+    #[error(
+        "The download of {package} is corrupt, expected {expected} bytes but received {received}."
+    )]
+    CorruptDownload {
+        package: String,
+        expected: u64,
+        received: u64,
+    },
+
+    #[error("The archive entry '{0}' would be extracted outside of its directory.")]
+    MaliciousArchive(String),
+
+    #[error("The {0} download ended early, the connection may have been interrupted.")]
+    TruncatedResponse(&'static str),
+
+    #[error("The {0} command doesn't support JSON output.")]
+    JsonOutputUnsupported(&'static str),
+}
+
+impl Error {
+    /// The stable code identifying this kind of error, taken from its discriminant.
+    pub fn code(&self) -> u32 {
+        // SAFETY: The enum is `repr(u32)`, so every variant begins with its u32 discriminant.
+        unsafe { *(self as *const Self).cast::<u32>() }
+    }
+
+    /// Structured details of the error, for consumers which shouldn't parse the message.
+    fn fields(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        match self {
+            Error::ApiError { response_body, .. } => json!({ "response_body": response_body }),
+            Error::ProjectDirIsFile(path)
+            | Error::ProjectAlreadyExists(path)
+            | Error::NoProjectFile(path)
+            | Error::ExeNotFound(path)
+            | Error::FileNotFound(path)
+            | Error::EmptyReadme(path)
+            | Error::InvalidLockFile(path, _) => json!({ "path": path }),
+            Error::FileIoError(path, e) => json!({ "path": path, "kind": e.kind().to_string() }),
+            Error::MaliciousArchive(entry) => json!({ "entry": entry }),
+            Error::MissingTable(table) => json!({ "table": table }),
+            Error::JsonOutputUnsupported(command) => json!({ "command": command }),
+            Error::InvalidGameId(game)
+            | Error::GameNotFound(game)
+            | Error::GameNotImported(game)
+            | Error::MissingR2ModManDefinition(game) => json!({ "game": game }),
+            Error::SteamAppNotFound { app_id, libraries } => {
+                json!({ "app_id": app_id, "libraries": libraries })
+            }
+            Error::WrongProjectKind { expected, found } => {
+                json!({ "expected": expected.to_string(), "found": found.to_string() })
+            }
+            Error::AuditFailed(count) | Error::ImportFailed(count) => json!({ "count": count }),
+            Error::PackageNotCached(package) => json!({ "package": package }),
+            Error::DeprecatedPackages(packages) | Error::NsfwPackages(packages) => {
+                json!({ "packages": packages })
+            }
+            Error::AmbiguousGameId { query, candidates } => {
+                json!({ "query": query, "candidates": candidates })
+            }
+            Error::CorruptDownload {
+                package,
+                expected,
+                received,
+            } => json!({ "package": package, "expected": expected, "received": received }),
+            Error::InvalidImportListLine { line, reason } => {
+                json!({ "line": line, "reason": reason })
+            }
+            _ => json!({}),
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("Error", 3)?;
+        error.serialize_field("error_code", &self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("fields", &self.fields())?;
+        error.end()
+    }
 }
 
 pub trait IoResultToTcli<R> {
@@ -91,3 +253,58 @@ impl From<walkdir::Error> for Error {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_stable() {
+        let api_error = Error::ApiError {
+            source: reqwest::Client::new().get("not a url").build().unwrap_err(),
+            response_body: None,
+        };
+        let json_error = serde_json::from_str::<()>("{").unwrap_err();
+
+        assert_eq!(api_error.code(), 1);
+        assert_eq!(Error::MissingRepository.code(), 12);
+        assert_eq!(Error::JsonParserError(json_error).code(), 15);
+        assert_eq!(Error::NoProjectFile(PathBuf::new()).code(), 9);
+        assert_eq!(Error::MaliciousArchive(String::new()).code(), 36);
+        assert_eq!(Error::TruncatedResponse("").code(), 37);
+    }
+
+    #[test]
+    fn serializes_code_message_and_fields() {
+        let error = Error::AmbiguousGameId {
+            query: "risk".to_string(),
+            candidates: vec!["ror2 (Risk of Rain 2)".to_string()],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "error_code": 34,
+                "message": error.to_string(),
+                "fields": {
+                    "query": "risk",
+                    "candidates": ["ror2 (Risk of Rain 2)"],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_empty_fields_for_unit_variant() {
+        assert_eq!(
+            serde_json::to_value(Error::MissingAuthToken).unwrap(),
+            json!({
+                "error_code": 13,
+                "message": Error::MissingAuthToken.to_string(),
+                "fields": {},
+            })
+        );
+    }
+}
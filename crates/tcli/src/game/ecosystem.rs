@@ -1,45 +1,105 @@
 use std::fs;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::BufReader;
+use std::path::PathBuf;
 
-use crate::error::Error;
-use crate::ts::v1::{ecosystem, models::ecosystem::EcosystemSchema};
-use crate::TCLI_HOME;
+use itertools::Itertools;
 
-pub async fn get_schema() -> Result<EcosystemSchema, Error> {
-    let local_schema = TCLI_HOME.join("ecosystem_schema.json");
+use crate::error::{Error, IoResultToTcli};
+use crate::ts::v1::ecosystem::{self, SchemaChannel};
+use crate::ts::v1::models::ecosystem::{EcosystemSchema, GameDef};
+use crate::util::file;
+use crate::TCLI_HOME;
 
-    match local_schema.is_file() {
-        true => {
-            let schema_file = File::open(&local_schema)?;
-            let reader = BufReader::new(&schema_file);
+/// Read the local ecosystem schema of the given channel, downloading it if there is none.
+pub async fn get_schema(channel: SchemaChannel) -> Result<EcosystemSchema, Error> {
+    match read_local_schema(channel)? {
+        Some(schema) => Ok(schema),
+        None => {
+            let schema = ecosystem::get_schema(channel).await?;
+            write_local_schema(channel, &schema)?;
 
-            Ok(serde_json::from_reader(reader).unwrap())
+            Ok(schema)
         }
+    }
+}
 
-        false => {
-            let schema_file = File::create(&local_schema)?;
-            let schema = ecosystem::get_schema().await?;
+/// Download the latest schema of the channel and replace its local copy with it, returning the
+/// previous local copy if there was one. The local copy is left untouched if the download fails.
+pub async fn update_schema(
+    channel: SchemaChannel,
+) -> Result<(Option<EcosystemSchema>, EcosystemSchema), Error> {
+    let new = ecosystem::get_schema(channel).await?;
+    // A local schema which can't be read is replaced all the same.
+    let current = read_local_schema(channel).ok().flatten();
 
-            let schema_writer = BufWriter::new(&schema_file);
-            serde_json::to_writer_pretty(schema_writer, &schema).unwrap();
+    write_local_schema(channel, &new)?;
 
-            Ok(schema)
-        }
+    Ok((current, new))
+}
+
+/// Find the games whose identifier or display name contains the query, ignoring case. A game
+/// whose identifier matches the query exactly is returned on its own.
+pub fn search_games<'a>(schema: &'a EcosystemSchema, query: &str) -> Vec<&'a GameDef> {
+    if let Some(game) = schema.games.get(query) {
+        return vec![game];
     }
+
+    let query = query.to_lowercase();
+
+    schema
+        .games
+        .values()
+        .filter(|x| {
+            x.label.to_lowercase().contains(&query)
+                || x.meta.display_name.to_lowercase().contains(&query)
+        })
+        .sorted_by(|a, b| a.label.cmp(&b.label))
+        .collect()
 }
 
-pub fn schema_exists() -> bool {
-    TCLI_HOME.join("ecosystem_schema.json").exists()
+/// The local copy of each channel's schema is kept separately, so that switching channels doesn't
+/// use a schema downloaded from the other.
+fn local_schema_path(channel: SchemaChannel) -> PathBuf {
+    TCLI_HOME.join(format!("ecosystem_schema.{}.json", channel.name()))
 }
 
-pub fn remove_schema() -> Result<(), Error> {
-    if !schema_exists() {
-        return Ok(());
+fn read_local_schema(channel: SchemaChannel) -> Result<Option<EcosystemSchema>, Error> {
+    let local_schema = local_schema_path(channel);
+
+    if !local_schema.is_file() {
+        return Ok(None);
     }
 
-    let schema = TCLI_HOME.join("ecosystem_schema.json");
-    fs::remove_file(&schema)?;
+    let schema_file = File::open(&local_schema).map_fs_error(&local_schema)?;
+    let reader = BufReader::new(&schema_file);
+
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+fn write_local_schema(channel: SchemaChannel, schema: &EcosystemSchema) -> Result<(), Error> {
+    let local_schema = local_schema_path(channel);
+
+    fs::create_dir_all(&*TCLI_HOME).map_fs_error(&*TCLI_HOME)?;
+    file::write_atomic(&local_schema, serde_json::to_vec_pretty(schema)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_schema_is_kept_per_channel() {
+        let schema = serde_json::from_str::<EcosystemSchema>(
+            r#"{ "schemaVersion": "0.0.11", "games": {}, "communities": {} }"#,
+        )
+        .unwrap();
 
-    Ok(())
+        write_local_schema(SchemaChannel::DevLatest, &schema).unwrap();
+
+        assert!(read_local_schema(SchemaChannel::Stable).unwrap().is_none());
+        assert!(read_local_schema(SchemaChannel::DevLatest)
+            .unwrap()
+            .is_some());
+    }
 }
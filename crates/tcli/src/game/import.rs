@@ -0,0 +1,250 @@
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use super::ecosystem;
+use super::import_list::ImportListEntry;
+use super::registry::{self, GameData, GameImportBuilder};
+use crate::error::Error;
+use crate::project::ProjectPath;
+use crate::ts::v1::ecosystem::SchemaChannel;
+use crate::ts::v1::models::ecosystem::EcosystemSchema;
+use crate::ui::prompt;
+
+/// Options applied to every game imported by [`import_games`].
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    pub custom_id: Option<String>,
+    pub custom_name: Option<String>,
+    pub exe_path: Option<PathBuf>,
+    pub steam_dir: Option<PathBuf>,
+    /// Platforms to search first, after the platform given by an import list entry.
+    pub platform_priority: Vec<String>,
+    /// Replace existing registry entries without asking.
+    pub yes: bool,
+}
+
+/// Choose the game matching the query, returning its identifier. Returns none if the user
+/// cancels choosing between multiple matches.
+///
+/// When the query matches multiple games the `select`th match is chosen, counting from 1, or the
+/// first match when `first` is set. Otherwise the user is asked to choose when running in a
+/// terminal, and the query is rejected as ambiguous when not.
+pub fn select_game(
+    schema: &EcosystemSchema,
+    query: &str,
+    select: Option<usize>,
+    first: bool,
+) -> Result<Option<String>, Error> {
+    let matches = ecosystem::search_games(schema, query);
+    let candidates = matches
+        .iter()
+        .map(|x| format!("{} ({})", x.label, x.meta.display_name))
+        .collect::<Vec<_>>();
+    let ambiguous = || Error::AmbiguousGameId {
+        query: query.to_string(),
+        candidates: candidates.clone(),
+    };
+
+    let index = match (matches.len(), select) {
+        (0, _) => return Err(Error::InvalidGameId(query.to_string())),
+        (1, _) => 0,
+        (_, Some(n)) => n
+            .checked_sub(1)
+            .filter(|x| *x < matches.len())
+            .ok_or_else(ambiguous)?,
+        _ if first => 0,
+        _ if std::io::stdin().is_terminal() => {
+            let question = format!("'{query}' matches multiple games:");
+            match prompt::select(&question, &candidates)? {
+                Some(index) => index,
+                None => return Ok(None),
+            }
+        }
+        _ => return Err(ambiguous()),
+    };
+
+    Ok(Some(matches[index].label.clone()))
+}
+
+/// Import each entry into the project's game registry. A batch reports each failed entry and
+/// continues with the rest, failing with [`Error::ImportFailed`] once every entry is imported,
+/// while a single import fails outright.
+pub async fn import_games(
+    project: &ProjectPath,
+    schema_channel: SchemaChannel,
+    entries: Vec<Result<ImportListEntry, Error>>,
+    options: &ImportOptions,
+    batch: bool,
+) -> Result<(), Error> {
+    let (mut imported, mut skipped, mut failed) = (0, 0, 0);
+
+    for entry in entries {
+        let label = match &entry {
+            Ok(entry) => format!("line {}, '{}': ", entry.line, entry.game_id),
+            Err(_) => String::new(),
+        };
+
+        let result = match entry {
+            Ok(entry) => import_game(project, schema_channel, entry, options, batch).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Err(e) if !batch => return Err(e),
+            Err(e) => {
+                eprintln!("{} {label}{e}", "[x]".bright_red());
+                failed += 1;
+            }
+            Ok(true) => imported += 1,
+            Ok(false) => skipped += 1,
+        }
+    }
+
+    if batch {
+        println!("\n{imported} imported, {skipped} skipped, {failed} failed.");
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(Error::ImportFailed(failed)),
+    }
+}
+
+/// Import a single game, returning whether it was written to the registry.
+async fn import_game(
+    project: &ProjectPath,
+    schema_channel: SchemaChannel,
+    entry: ImportListEntry,
+    options: &ImportOptions,
+    batch: bool,
+) -> Result<bool, Error> {
+    let platform_priority = entry
+        .platform
+        .into_iter()
+        .chain(options.platform_priority.iter().cloned())
+        .collect();
+
+    let data = GameImportBuilder::new(&entry.game_id, schema_channel)
+        .await?
+        .with_custom_id(options.custom_id.clone())
+        .with_custom_name(options.custom_name.clone())
+        .with_custom_exe(options.exe_path.clone())
+        .with_steam_dir(options.steam_dir.clone())
+        .with_platform_priority(platform_priority)
+        .resolve()?;
+
+    let existing =
+        registry::get_game_data(project, &data.identifier).filter(|existing| *existing != data);
+
+    if let Some(existing) = existing {
+        print_replacement(&existing, &data);
+
+        // Non-interactive sessions, such as scripts, proceed with the change logged above.
+        let interactive = std::io::stdin().is_terminal();
+        if !options.yes && interactive && !prompt::confirm("Overwrite the existing entry?")? {
+            return Ok(false);
+        }
+    }
+
+    if batch {
+        println!("Imported '{}'", data.identifier);
+    }
+    registry::write_data(project, data)?;
+
+    Ok(true)
+}
+
+/// Warn how the existing registry entry of a game differs from its new entry.
+fn print_replacement(existing: &GameData, data: &GameData) {
+    let old = &existing.active_distribution;
+    let new = &data.active_distribution;
+
+    eprintln!(
+        "{} '{}' is already imported, its entry will be replaced:",
+        "[!]".bright_yellow(),
+        data.identifier
+    );
+    eprintln!("- platform: {} -> {}", old.dist.name(), new.dist.name());
+    eprintln!(
+        "- game dir: {} -> {}",
+        old.game_dir.display(),
+        new.game_dir.display()
+    );
+    eprintln!(
+        "- data dir: {} -> {}",
+        old.data_dir.display(),
+        new.data_dir.display()
+    );
+    eprintln!(
+        "- exe path: {} -> {}",
+        old.exe_path.display(),
+        new.exe_path.display()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> EcosystemSchema {
+        let game = |label: &str, display_name: &str| {
+            json!({
+                "uuid": label,
+                "label": label,
+                "meta": { "displayName": display_name },
+                "distributions": [],
+            })
+        };
+
+        serde_json::from_value(json!({
+            "schemaVersion": "0.0.11",
+            "games": {
+                "riskofrain2": game("riskofrain2", "Risk of Rain 2"),
+                "riskofrainreturns": game("riskofrainreturns", "Risk of Rain Returns"),
+                "valheim": game("valheim", "Valheim"),
+            },
+            "communities": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn single_match_is_chosen() {
+        let game = select_game(&schema(), "valheim", None, false).unwrap();
+        assert_eq!(game.as_deref(), Some("valheim"));
+    }
+
+    #[test]
+    fn select_chooses_nth_match() {
+        let game = select_game(&schema(), "risk", Some(2), false).unwrap();
+        assert_eq!(game.as_deref(), Some("riskofrainreturns"));
+    }
+
+    #[test]
+    fn select_out_of_range_is_ambiguous() {
+        for select in [0, 3] {
+            assert!(matches!(
+                select_game(&schema(), "risk", Some(select), false),
+                Err(Error::AmbiguousGameId { candidates, .. }) if candidates.len() == 2
+            ));
+        }
+    }
+
+    #[test]
+    fn first_chooses_first_match() {
+        let game = select_game(&schema(), "risk", None, true).unwrap();
+        assert_eq!(game.as_deref(), Some("riskofrain2"));
+    }
+
+    #[test]
+    fn no_match_is_invalid() {
+        assert!(matches!(
+            select_game(&schema(), "minecraft", None, true),
+            Err(Error::InvalidGameId(query)) if query == "minecraft"
+        ));
+    }
+}
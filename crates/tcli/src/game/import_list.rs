@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, IoResultToTcli};
+use crate::ts::v1::models::ecosystem::GameDefPlatform;
+
+/// A single game within an import list.
+#[derive(Debug, Clone)]
+pub struct ImportListEntry {
+    pub line: usize,
+    pub game_id: String,
+    /// The platform to search for the game first, if the line specifies one.
+    pub platform: Option<String>,
+}
+
+/// Read a newline delimited list of games to import. Each line contains a game identifier,
+/// optionally followed by the platform to search first, for example `riskofrain2 steam`. Blank
+/// lines and lines starting with `#` are ignored.
+///
+/// Lines are parsed independently so that a single malformed line doesn't prevent the rest of
+/// the list from being imported.
+pub fn read(path: &Path) -> Result<Vec<Result<ImportListEntry, Error>>, Error> {
+    let contents = fs::read_to_string(path).map_fs_error(path)?;
+
+    Ok(parse(&contents))
+}
+
+fn parse(contents: &str) -> Vec<Result<ImportListEntry, Error>> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line, text)| parse_line(line, text))
+        .collect()
+}
+
+fn parse_line(line: usize, text: &str) -> Result<ImportListEntry, Error> {
+    let invalid = |reason: String| Error::InvalidImportListLine { line, reason };
+
+    let mut parts = text.split_whitespace();
+    let game_id = parts.next().unwrap().to_string();
+    let platform = parts.next().map(|x| x.to_string());
+
+    if let Some(extra) = parts.next() {
+        return Err(invalid(format!("unexpected '{extra}' after the platform")));
+    }
+
+    if let Some(platform) = platform
+        .as_deref()
+        .filter(|x| !GameDefPlatform::NAMES.contains(x))
+    {
+        return Err(invalid(format!(
+            "'{platform}' is not a platform, expected one of {}",
+            GameDefPlatform::NAMES.join(", ")
+        )));
+    }
+
+    Ok(ImportListEntry {
+        line,
+        game_id,
+        platform,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let entries =
+            parse("# games to import\n\n  \nriskofrain2\n  # indented comment\nvalheim\n");
+
+        let entries = entries
+            .into_iter()
+            .map(|x| x.unwrap())
+            .map(|x| (x.line, x.game_id, x.platform))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            entries,
+            [
+                (4, "riskofrain2".to_string(), None),
+                (6, "valheim".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_platform_override() {
+        let entry = parse_line(1, "riskofrain2 steam").unwrap();
+
+        assert_eq!(entry.game_id, "riskofrain2");
+        assert_eq!(entry.platform.as_deref(), Some("steam"));
+    }
+
+    #[test]
+    fn rejects_unknown_platform() {
+        assert!(matches!(
+            parse_line(3, "riskofrain2 gog"),
+            Err(Error::InvalidImportListLine { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_trailing_text() {
+        assert!(matches!(
+            parse_line(2, "riskofrain2 steam extra"),
+            Err(Error::InvalidImportListLine { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_line_does_not_stop_others() {
+        let entries = parse("riskofrain2 gog\nvalheim\n");
+
+        assert!(entries[0].is_err());
+        assert_eq!(entries[1].as_ref().unwrap().game_id, "valheim");
+    }
+}
@@ -1,4 +1,6 @@
 pub mod ecosystem;
+pub mod import;
+pub mod import_list;
 pub mod registry;
 mod steam;
 
@@ -1,13 +1,16 @@
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::fs;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use super::{ecosystem, steam};
-use crate::error::Error;
+use crate::error::{Error, IoResultToTcli};
 use crate::project::ProjectPath;
+use crate::ts::v1::ecosystem::SchemaChannel;
 use crate::ts::v1::models::ecosystem::{GameDef, GameDefPlatform};
+use crate::util::file;
 use crate::util::os::OS;
 
 #[cfg(windows)]
@@ -28,6 +31,9 @@ pub struct ActiveDistribution {
     pub game_dir: PathBuf,
     pub data_dir: PathBuf,
     pub exe_path: PathBuf,
+    /// The Wine prefix the game runs within, for Windows games run through Proton.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wine_prefix: Option<PathBuf>,
 }
 
 pub struct GameImportBuilder {
@@ -35,11 +41,13 @@ pub struct GameImportBuilder {
     custom_id: Option<String>,
     custom_name: Option<String>,
     custom_exe: Option<PathBuf>,
+    steam_dir: Option<PathBuf>,
+    platform_priority: Vec<String>,
 }
 
 impl GameImportBuilder {
-    pub async fn new(game_id: &str) -> Result<Self, Error> {
-        let game_def = ecosystem::get_schema()
+    pub async fn new(game_id: &str, schema_channel: SchemaChannel) -> Result<Self, Error> {
+        let game_def = ecosystem::get_schema(schema_channel)
             .await?
             .games
             .get(game_id)
@@ -51,6 +59,8 @@ impl GameImportBuilder {
             custom_id: None,
             custom_name: None,
             custom_exe: None,
+            steam_dir: None,
+            platform_priority: vec![],
         })
     }
 
@@ -69,21 +79,51 @@ impl GameImportBuilder {
         GameImportBuilder { custom_exe, ..self }
     }
 
-    /// Import the game as a new game definition, automatically determining the
-    /// correct platform to use.
+    pub fn with_steam_dir(self, steam_dir: Option<PathBuf>) -> Self {
+        GameImportBuilder { steam_dir, ..self }
+    }
+
+    /// Set the order in which platforms are searched for the game. Platforms not within the list
+    /// are searched afterwards in the order they are defined within the ecosystem schema.
+    pub fn with_platform_priority(self, platform_priority: Vec<String>) -> Self {
+        GameImportBuilder {
+            platform_priority,
+            ..self
+        }
+    }
+
+    /// Resolve the game into a new game definition, automatically determining the correct
+    /// platform to use. The definition is imported by passing it to [`write_data`].
     ///
-    /// Note that this function does not yet support Linux native Wine interop. The Windows native
-    /// build of tcli must be run through Wine to detect games installed to the current prefix.
-    pub fn import(self, project: &ProjectPath) -> Result<(), Error> {
+    /// On Linux, the Proton prefix of games found through Steam is resolved as well. Native Linux
+    /// games, and games which haven't been launched through Proton yet, have no prefix. Other
+    /// platforms don't support Linux native Wine interop yet. To detect those games, the Windows
+    /// build of tcli must be run through Wine.
+    pub fn resolve(self) -> Result<GameData, Error> {
+        // Kept so that a failed Steam lookup can be reported if no other platform matches.
+        let mut steam_error = None;
+
         let (dist, game_dir) = self
             .game_def
             .distributions
             .iter()
+            .sorted_by_key(|dist| {
+                self.platform_priority
+                    .iter()
+                    .position(|x| x == dist.name())
+                    .unwrap_or(usize::MAX)
+            })
             .find_map(|dist| match dist {
                 GameDefPlatform::Steam { identifier } => {
                     let id = identifier.parse::<u32>().unwrap();
 
-                    steam::get_game_path(id).map(|x| (dist, x))
+                    match steam::get_game_path(id, self.steam_dir.as_deref()) {
+                        Ok(x) => Some((dist, x)),
+                        Err(e) => {
+                            steam_error = Some(e);
+                            None
+                        }
+                    }
                 }
 
                 #[cfg(windows)]
@@ -109,48 +149,49 @@ impl GameImportBuilder {
 
                 _ => None,
             })
-            .unwrap();
+            .ok_or_else(|| {
+                steam_error.unwrap_or_else(|| Error::GameNotFound(self.game_def.label.clone()))
+            })?;
 
-        let r2modman = self.game_def.r2modman.unwrap();
+        let r2modman = self
+            .game_def
+            .r2modman
+            .ok_or_else(|| Error::MissingR2ModManDefinition(self.game_def.label.clone()))?;
         let game_dir = game_dir.canonicalize()?;
         let data_dir = game_dir.join(r2modman.data_folder_name);
 
         // TODO: Determine the path of the game's executable via the platform.
-        let exe_path = self
-            .custom_exe
-            .unwrap_or_else(|| {
-                r2modman
-                    .exe_names
-                    .iter()
-                    .find_map(|x| {
-                        let exe_path = game_dir.join(x);
-
-                        if exe_path.exists() {
-                            Some(exe_path)
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap()
-            })
-            .canonicalize()?;
+        let exe_path = match self.custom_exe {
+            Some(exe_path) if !exe_path.is_file() => return Err(Error::FileNotFound(exe_path)),
+            Some(exe_path) => exe_path,
+            None => find_exe(&game_dir, &r2modman.exe_names)
+                .ok_or_else(|| Error::ExeNotFound(game_dir.clone()))?,
+        };
+        let exe_path = exe_path.canonicalize().map_fs_error(&exe_path)?;
+
+        let wine_prefix = match dist {
+            GameDefPlatform::Steam { identifier } if cfg!(target_os = "linux") => {
+                let id = identifier.parse::<u32>().unwrap();
+                steam::get_proton_prefix(id, self.steam_dir.as_deref())?
+            }
+            _ => None,
+        };
 
         let active_dist = ActiveDistribution {
             dist: dist.to_owned(),
             game_dir,
             data_dir,
             exe_path,
+            wine_prefix,
         };
 
-        let data = GameData {
+        Ok(GameData {
             identifier: self.custom_id.unwrap_or(self.game_def.label.clone()),
             ecosystem_label: self.game_def.label,
             display_name: self.custom_name.unwrap_or(self.game_def.meta.display_name),
             active_distribution: active_dist,
             possible_distributions: self.game_def.distributions,
-        };
-
-        write_data(project, data)
+        })
     }
 
     pub fn as_steam(self) -> SteamImportBuilder {
@@ -175,6 +216,30 @@ impl SteamImportBuilder {
     }
 }
 
+/// Detect the executable of an imported game within its game directory, using the executable
+/// names of the game's ecosystem schema entry.
+pub async fn detect_exe(data: &GameData, schema_channel: SchemaChannel) -> Result<PathBuf, Error> {
+    let schema = ecosystem::get_schema(schema_channel).await?;
+    let r2modman = schema
+        .games
+        .get(&data.ecosystem_label)
+        .ok_or_else(|| Error::InvalidGameId(data.ecosystem_label.clone()))?
+        .r2modman
+        .as_ref()
+        .ok_or_else(|| Error::MissingR2ModManDefinition(data.ecosystem_label.clone()))?;
+
+    let game_dir = &data.active_distribution.game_dir;
+    find_exe(game_dir, &r2modman.exe_names).ok_or_else(|| Error::ExeNotFound(game_dir.clone()))
+}
+
+/// Find the first of the game's known executables which exists within the game directory.
+pub fn find_exe(game_dir: &Path, exe_names: &[String]) -> Option<PathBuf> {
+    exe_names
+        .iter()
+        .map(|x| game_dir.join(x))
+        .find(|x| x.exists())
+}
+
 pub fn get_supported_platforms(target_os: &OS) -> Vec<&'static str> {
     let mut platforms = vec!["Steam", "DRM Free"];
 
@@ -203,32 +268,109 @@ pub fn get_game_data(project: &ProjectPath, game_id: &str) -> Option<GameData> {
     game_registry.into_iter().find(|x| x.identifier == game_id)
 }
 
-fn write_data(project: &ProjectPath, data: GameData) -> Result<(), Error> {
-    let game_registry = project.path().join(".tcli/game_registry.json");
+/// Write the game into the project's registry, replacing any existing entry with the same
+/// identifier.
+pub fn write_data(project: &ProjectPath, data: GameData) -> Result<(), Error> {
+    let path = project.path().join(".tcli/game_registry.json");
 
-    let mut file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .open(&game_registry)?;
+    let mut game_registry: Vec<GameData> = match fs::read_to_string(&path) {
+        Ok(contents) if contents.is_empty() => Vec::new(),
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(Error::FileIoError(path, e)),
+    };
 
-    let mut game_registry: Vec<GameData> = {
-        let contents = fs::read_to_string(&game_registry)?;
+    match game_registry
+        .iter_mut()
+        .find(|x| x.identifier == data.identifier)
+    {
+        Some(existing) => *existing = data,
+        None => game_registry.push(data),
+    }
 
-        if contents.is_empty() {
-            Vec::new()
-        } else {
-            serde_json::from_str(&contents).unwrap()
-        }
+    let data_json = serde_json::to_string_pretty(&game_registry).unwrap();
+    file::write_atomic(&path, data_json)?;
+
+    Ok(())
+}
+
+/// Remove the game with the given identifier from the project's registry. Returns whether the
+/// game had been imported.
+pub fn remove_data(project: &ProjectPath, game_id: &str) -> Result<bool, Error> {
+    let path = project.path().join(".tcli/game_registry.json");
+
+    let mut game_registry: Vec<GameData> = match fs::read_to_string(&path) {
+        Ok(contents) if contents.is_empty() => return Ok(false),
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(Error::FileIoError(path, e)),
     };
 
-    if game_registry.contains(&data) {
-        return Ok(());
-    }
+    let count = game_registry.len();
+    game_registry.retain(|x| x.identifier != game_id);
 
-    game_registry.push(data);
+    if game_registry.len() == count {
+        return Ok(false);
+    }
 
     let data_json = serde_json::to_string_pretty(&game_registry).unwrap();
-    file.write_all(data_json.as_bytes())?;
+    file::write_atomic(&path, data_json)?;
 
-    Ok(())
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(identifier: &str, display_name: &str) -> GameData {
+        GameData {
+            ecosystem_label: identifier.to_string(),
+            identifier: identifier.to_string(),
+            display_name: display_name.to_string(),
+            active_distribution: ActiveDistribution {
+                dist: GameDefPlatform::Other,
+                game_dir: PathBuf::from("game"),
+                data_dir: PathBuf::from("game/data"),
+                exe_path: PathBuf::from("game/game.exe"),
+                wine_prefix: None,
+            },
+            possible_distributions: vec![GameDefPlatform::Other],
+        }
+    }
+
+    fn project(dir: &Path) -> ProjectPath {
+        fs::write(dir.join("Thunderstore.toml"), "").unwrap();
+        ProjectPath::new(dir).unwrap()
+    }
+
+    #[test]
+    fn write_data_replaces_with_shorter_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project(dir.path());
+
+        let long_name = "a very long display name ".repeat(8);
+        write_data(&project, game("first", &long_name)).unwrap();
+        write_data(&project, game("second", "Second")).unwrap();
+        write_data(&project, game("first", "First")).unwrap();
+
+        assert_eq!(
+            get_registry(&project).unwrap(),
+            [game("first", "First"), game("second", "Second")]
+        );
+    }
+
+    #[test]
+    fn remove_data_shrinks_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project(dir.path());
+
+        write_data(&project, game("first", "First")).unwrap();
+        write_data(&project, game("second", "Second")).unwrap();
+
+        assert!(remove_data(&project, "first").unwrap());
+        assert!(!remove_data(&project, "first").unwrap());
+        assert_eq!(get_registry(&project).unwrap(), [game("second", "Second")]);
+        assert_eq!(get_game_data(&project, "first"), None);
+    }
 }
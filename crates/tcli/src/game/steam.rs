@@ -1,10 +1,140 @@
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use steamlocate::SteamDir;
 
-pub fn get_game_path(id: u32) -> Option<PathBuf> {
-    let mut steam = SteamDir::locate()?;
-    let app = steam.app(&id)?;
+use crate::error::Error;
 
-    Some(app.path.to_owned())
+/// Find the install path of a Steam app, searching the given Steam installation or the
+/// automatically located one if none is given.
+///
+/// Every library folder listed within `libraryfolders.vdf` is searched in order, so games
+/// installed to a secondary library on another drive are found as well.
+pub fn get_game_path(id: u32, steam_dir: Option<&Path>) -> Result<PathBuf, Error> {
+    let libraries = get_libraries(steam_dir)?;
+
+    libraries
+        .iter()
+        .find_map(|library| find_app_in_library(library, id))
+        .ok_or(Error::SteamAppNotFound {
+            app_id: id,
+            libraries,
+        })
+}
+
+/// Find the Proton prefix of a Steam app, which Steam creates within the `compatdata` folder of
+/// the app's library the first time it's launched through Proton. Native Linux games don't run
+/// through Proton and so have no prefix.
+pub fn get_proton_prefix(id: u32, steam_dir: Option<&Path>) -> Result<Option<PathBuf>, Error> {
+    Ok(get_libraries(steam_dir)?
+        .iter()
+        .map(|library| library.join("compatdata").join(id.to_string()).join("pfx"))
+        .find(|prefix| prefix.is_dir()))
+}
+
+/// The `steamapps` folder of every library within the given Steam installation, or the
+/// automatically located one if none is given.
+fn get_libraries(steam_dir: Option<&Path>) -> Result<Vec<PathBuf>, Error> {
+    let mut steam = match steam_dir {
+        Some(path) => {
+            let mut steam = SteamDir::default();
+            steam.path = path.to_path_buf();
+            steam
+        }
+        None => SteamDir::locate().ok_or(Error::SteamDirNotFound)?,
+    };
+
+    Ok(steam.libraryfolders().paths.clone())
+}
+
+/// Search a single library's `steamapps` folder for the app manifest of the given app id,
+/// returning the app's install directory if it exists.
+fn find_app_in_library(library: &Path, id: u32) -> Option<PathBuf> {
+    let manifest = fs::read_to_string(library.join(format!("appmanifest_{id}.acf"))).ok()?;
+
+    // We only need the install directory, so read it straight from the manifest's
+    // `"installdir"		"<name>"` line rather than requiring every field to be present.
+    let install_dir = manifest.lines().find_map(|line| {
+        let mut parts = line.split('"').filter(|x| !x.trim().is_empty());
+
+        match parts.next()? {
+            "installdir" => parts.next(),
+            _ => None,
+        }
+    })?;
+
+    let game_path = library.join("common").join(install_dir);
+    game_path.is_dir().then_some(game_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a Steam installation whose second library contains a single app.
+    fn steam_with_app(root: &Path, id: u32) -> (PathBuf, PathBuf) {
+        let steam_dir = root.join("steam");
+        let library = root.join("library");
+
+        fs::create_dir_all(steam_dir.join("steamapps")).unwrap();
+        fs::create_dir_all(library.join("steamapps/common/Game")).unwrap();
+
+        // Backslashes are escaped within VDF strings, as Steam does for Windows paths.
+        let vdf_path = |path: &Path| path.display().to_string().replace('\\', r"\\");
+        let library_folders = format!(
+            r#""libraryfolders" {{ "0" {{ "path" "{}" }} "1" {{ "path" "{}" }} }}"#,
+            vdf_path(&steam_dir),
+            vdf_path(&library),
+        );
+        fs::write(
+            steam_dir.join("steamapps/libraryfolders.vdf"),
+            library_folders,
+        )
+        .unwrap();
+
+        fs::write(
+            library.join(format!("steamapps/appmanifest_{id}.acf")),
+            "\"AppState\"\n{\n\t\"installdir\"\t\t\"Game\"\n}\n",
+        )
+        .unwrap();
+
+        (steam_dir, library.join("steamapps"))
+    }
+
+    #[test]
+    fn game_path_is_found_in_secondary_library() {
+        let root = tempfile::tempdir().unwrap();
+        let (steam_dir, library) = steam_with_app(root.path(), 12345);
+
+        assert_eq!(
+            get_game_path(12345, Some(&steam_dir)).unwrap(),
+            library.join("common/Game")
+        );
+        assert!(matches!(
+            get_game_path(54321, Some(&steam_dir)),
+            Err(Error::SteamAppNotFound { app_id: 54321, .. })
+        ));
+    }
+
+    #[test]
+    fn native_game_has_no_proton_prefix() {
+        let root = tempfile::tempdir().unwrap();
+        let (steam_dir, _) = steam_with_app(root.path(), 12345);
+
+        assert_eq!(get_proton_prefix(12345, Some(&steam_dir)).unwrap(), None);
+    }
+
+    #[test]
+    fn proton_prefix_is_found_in_compatdata() {
+        let root = tempfile::tempdir().unwrap();
+        let (steam_dir, library) = steam_with_app(root.path(), 12345);
+
+        let prefix = library.join("compatdata/12345/pfx");
+        fs::create_dir_all(&prefix).unwrap();
+
+        assert_eq!(
+            get_proton_prefix(12345, Some(&steam_dir)).unwrap(),
+            Some(prefix)
+        );
+    }
 }
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+use once_cell::sync::Lazy;
+
+pub use crate::error::Error;
+pub use crate::package::resolver::PackageResolver;
+pub use crate::project::ProjectPath;
+pub use crate::ui::reporter::Reporter;
+
+pub mod config;
+pub mod error;
+pub mod game;
+pub mod package;
+pub mod project;
+pub mod ts;
+pub mod ui;
+pub mod util;
+
+pub static TCLI_HOME: Lazy<PathBuf> = Lazy::new(|| {
+    // Tests must never modify the user's real home, such as when recording known projects.
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("tcli-test-{}", std::process::id()));
+    }
+
+    let default_home = BaseDirs::new().unwrap().data_dir().join("tcli");
+
+    config::Vars::HomeDir
+        .into_var()
+        .map_or_else(|_| default_home, PathBuf::from)
+});
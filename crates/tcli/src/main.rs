@@ -1,46 +1,71 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use clap::Parser;
-use colored::Colorize;
+use clap::{Parser, ValueEnum};
 use cli::InitSubcommand;
-use directories::BaseDirs;
-use once_cell::sync::Lazy;
-use package::resolver::PackageResolver;
-use project::ProjectKind;
+use colored::Colorize;
+use itertools::Itertools;
+use tcli::config::{Config, Vars};
+use tcli::error::{Error, IoResultToTcli};
+use tcli::game::import::{self, ImportOptions};
+use tcli::game::import_list::{self, ImportListEntry};
+use tcli::game::{ecosystem, registry};
+use tcli::package::cache;
+use tcli::package::resolver::PackageResolver;
+use tcli::project::known;
+use tcli::project::lock::LockFile;
+use tcli::project::manifest::ProjectManifest;
+use tcli::project::overrides::ProjectOverrides;
+use tcli::project::{self, ProjectKind, ProjectPath};
+use tcli::ts::experimental::models::package::PackageMetadata;
+use tcli::ts::v1::ecosystem::SchemaChannel;
+use tcli::ui::prompt;
+use tcli::ui::reporter::{
+    Event, IndicatifReporter, JsonlReporter, Reporter, SilentReporter, VoidReporter,
+};
+use tcli::util::SecretString;
+use tcli::{package, ts};
 use wildmatch::WildMatch;
 
-use crate::cli::{Args, Commands, ListSubcommand};
-use crate::config::Vars;
-use crate::error::Error;
-use crate::game::registry::GameImportBuilder;
-use crate::game::{ecosystem, registry};
-use crate::project::lock::LockFile;
-use crate::project::manifest::ProjectManifest;
-use crate::project::overrides::ProjectOverrides;
-use crate::project::ProjectPath;
-use crate::ui::reporter::IndicatifReporter;
+use crate::cli::{
+    Args, CacheSubcommand, Commands, ConfigKey, ConfigSubcommand, GamesSubcommand, ListSubcommand,
+    OutputFormat, PackageSubcommand, ProjectsSubcommand, ReportFormat, SchemaChannelArg,
+};
 
 mod cli;
-mod config;
-mod error;
-mod game;
-mod package;
-mod project;
-mod ts;
-mod ui;
-mod util;
-
-pub static TCLI_HOME: Lazy<PathBuf> = Lazy::new(|| {
-    let default_home = BaseDirs::new().unwrap().data_dir().join("tcli");
-
-    Vars::HomeDir
-        .into_var()
-        .map_or_else(|_| default_home, PathBuf::from)
-});
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    match Args::parse().commands {
+    let mut args = Args::parse();
+    if args.json {
+        args.output = OutputFormat::Json;
+    }
+
+    let json_errors = args.json_errors || args.output != OutputFormat::Human;
+
+    match run(args).await {
+        Err(e) if json_errors => {
+            eprintln!("{}", serde_json::to_string(&e).unwrap());
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+async fn run(args: Args) -> Result<(), Error> {
+    if args.trace_network {
+        ts::enable_network_trace();
+    }
+
+    // Commands with their own --json flag also honour the global output format.
+    let json_output = args.output == OutputFormat::Json;
+    if json_output {
+        colored::control::set_override(false);
+    }
+
+    match args.commands {
         Commands::Init {
             command,
             overwrite,
@@ -53,22 +78,26 @@ async fn main() -> Result<(), Error> {
             } => project::create_new(
                 &project_path,
                 overwrite,
-                ProjectKind::Dev(
-                    ProjectOverrides::new()
-                        .namespace_override(package_namespace)
-                        .name_override(package_name)
-                        .version_override(package_version),
-                ),
+                ProjectKind::Dev,
+                ProjectOverrides::new()
+                    .namespace_override(package_namespace)
+                    .name_override(package_name)
+                    .version_override(package_version),
+            ),
+            InitSubcommand::Profile => project::create_new(
+                &project_path,
+                overwrite,
+                ProjectKind::Profile,
+                ProjectOverrides::new(),
             ),
-            InitSubcommand::Profile => {
-                project::create_new(&project_path, overwrite, ProjectKind::Profile)
-            }
         },
         Commands::Build {
             package_name,
             package_namespace,
             package_version,
             output_dir,
+            reproducible,
+            emit,
             project_path,
         } => {
             let mut manifest = ProjectManifest::read_from_file(project_path)?;
@@ -77,9 +106,20 @@ async fn main() -> Result<(), Error> {
                     .namespace_override(package_namespace)
                     .name_override(package_name)
                     .version_override(package_version)
-                    .output_dir_override(output_dir),
+                    .output_dir_override(output_dir)
+                    .reproducible_override(reproducible.then_some(true)),
             )?;
-            project::build(&manifest)?;
+            let output = project::build(&manifest, emit.into())?;
+
+            if json_output {
+                let output = serde_json::json!({
+                    "output_path": output.archive.as_ref().or(output.directory.as_ref()),
+                    "archive": output.archive,
+                    "directory": output.directory,
+                });
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            }
+
             Ok(())
         }
         Commands::Publish {
@@ -91,7 +131,7 @@ async fn main() -> Result<(), Error> {
             repository,
             project_path,
         } => {
-            token = token.or_else(|| Vars::AuthKey.into_var().ok());
+            token = token.or_else(|| Vars::AuthKey.into_var().ok().map(SecretString::from));
             if token.is_none() {
                 return Err(Error::MissingAuthToken);
             }
@@ -109,71 +149,572 @@ async fn main() -> Result<(), Error> {
                     .repository
                     .as_deref()
                     .ok_or(Error::MissingRepository)?,
-                token.as_deref(),
+                token.as_ref(),
             );
-            project::publish(&manifest, file).await
+            project::publish(manifest, file).await
         }
         Commands::Add {
             packages,
+            cache_dir,
+            ignore_locked,
+            report,
+            explain,
+            no_deprecated,
+            block_nsfw,
+            dry_run,
+            quiet,
+            project_path,
+        } => {
+            ts::init_repository("https://thunderstore.io", None);
+
+            let reporter: Box<dyn Reporter> = match args.output {
+                OutputFormat::Human if quiet => Box::new(SilentReporter),
+                OutputFormat::Human => Box::new(IndicatifReporter),
+                OutputFormat::Json => Box::new(VoidReporter),
+                OutputFormat::Jsonl => Box::new(JsonlReporter),
+            };
+            let warn = |message: String| match args.output {
+                OutputFormat::Human => eprintln!("{} {message}", "[!]".bright_yellow()),
+                OutputFormat::Json => eprintln!("[!] {message}"),
+                OutputFormat::Jsonl => reporter.event(&Event::Warning { message }),
+            };
+            let project_path = ProjectPath::new(&project_path)?;
+
+            let started = Instant::now();
+            reporter.event(&Event::ResolveStarted {
+                packages: packages.len(),
+            });
+
+            let resolved = match cache_dir {
+                Some(cache_dir) => {
+                    PackageResolver::resolve_with_cache(packages.clone(), &project_path, &cache_dir)
+                        .await?
+                }
+                None => PackageResolver::resolve_new(packages.clone(), &project_path).await?,
+            };
+
+            reporter.event(&Event::ResolveFinished {
+                packages: resolved.packages_to_install.len(),
+                elapsed_ms: started.elapsed().as_millis(),
+            });
+
+            let resolved = resolved.ignore_locked(ignore_locked);
+
+            // Planning looks up the download size of every remote package, so it's skipped for
+            // plain installs which don't show the plan.
+            if dry_run || explain || args.output == OutputFormat::Jsonl {
+                let plan = resolved.plan(&packages).await?;
+                match args.output {
+                    OutputFormat::Human => println!("{plan}"),
+                    OutputFormat::Json if dry_run => {
+                        println!("{}", serde_json::to_string_pretty(&plan).unwrap())
+                    }
+                    OutputFormat::Json => (),
+                    OutputFormat::Jsonl => reporter.event(&Event::Plan { plan }),
+                }
+            }
+
+            if explain && args.output == OutputFormat::Human {
+                for explanation in resolved.explain(&packages) {
+                    println!("{explanation}");
+                }
+            }
+
+            let block_nsfw = block_nsfw || Config::load(project_path.path())?.block_nsfw;
+
+            // Listings are only needed for warnings unless a flag is being enforced, in which case
+            // an unreachable repository must not let flagged packages through.
+            let listings = match resolved.listings().await {
+                Ok(listings) => listings,
+                Err(e) if block_nsfw || no_deprecated => return Err(e),
+                Err(_) => {
+                    warn("could not fetch package listings, deprecated and NSFW packages were not checked".to_string());
+                    vec![]
+                }
+            };
+            let flagged = |flag: fn(&PackageMetadata) -> bool| {
+                listings
+                    .iter()
+                    .filter(|(_, listing)| flag(listing))
+                    .map(|(package, _)| package.identifier.to_string())
+                    .sorted()
+                    .collect::<Vec<_>>()
+            };
+
+            let deprecated = flagged(|x| x.is_deprecated);
+            let nsfw = flagged(|x| x.community_listings.iter().any(|x| x.has_nsfw_content));
+
+            if no_deprecated && !deprecated.is_empty() {
+                return Err(Error::DeprecatedPackages(deprecated));
+            }
+
+            if block_nsfw && !nsfw.is_empty() {
+                return Err(Error::NsfwPackages(nsfw));
+            }
+
+            for package in nsfw {
+                warn(format!("{package} is flagged as containing NSFW content"));
+            }
+
+            for package in deprecated {
+                warn(format!(
+                    "{package} is deprecated and may no longer be maintained"
+                ));
+            }
+
+            if ignore_locked {
+                warn("ignoring the existing lockfile, previously installed packages will no longer be tracked and must be added again".to_string());
+            }
+
+            if dry_run {
+                return Ok(());
+            }
+
+            let install_report = resolved.apply(&*reporter).await?;
+
+            reporter.event(&Event::Summary {
+                report: install_report,
+            });
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&install_report).unwrap());
+            }
+
+            match report.filter(|_| args.output == OutputFormat::Human) {
+                Some(ReportFormat::Text) => println!("{install_report}"),
+                Some(ReportFormat::Json) => {
+                    println!("{}", serde_json::to_string_pretty(&install_report).unwrap())
+                }
+                None => (),
+            }
+
+            Ok(())
+        }
+        Commands::UninstallAll { yes, project_path } => {
+            let project_path = ProjectPath::new(&project_path)?;
+            let lock = LockFile::open_or_new(&project_path.path().join("Thunderstore.lock"))?;
+
+            let count = match lock.packages.len() {
+                0 => 0,
+                count => {
+                    let question = format!(
+                        "Uninstall all {count} packages from {}?",
+                        project_path.path().display()
+                    );
+                    if !yes && !prompt::confirm(&question)? {
+                        return Ok(());
+                    }
+
+                    project::uninstall_all(&project_path)?
+                }
+            };
+
+            match json_output {
+                true => println!("{}", serde_json::json!({ "uninstalled": count })),
+                false if count == 0 => println!("No packages are installed."),
+                false => println!("Uninstalled {count} packages."),
+            }
+
+            Ok(())
+        }
+        Commands::Clean {
+            dry_run,
+            yes,
+            project_path,
+        } => {
+            let project_path = ProjectPath::new(&project_path)?;
+
+            // Without a lockfile every installed package is untracked, so removal is confirmed.
+            let untracked = project::clean(&project_path, true)?;
+            if !dry_run && !yes && !untracked.is_empty() {
+                let question = format!(
+                    "Remove {} untracked entries from {}?",
+                    untracked.len(),
+                    project_path.path().join("project_state").display()
+                );
+                if !prompt::confirm(&question)? {
+                    return Ok(());
+                }
+            }
+
+            let removed = match dry_run {
+                true => untracked,
+                false => project::clean(&project_path, false)?,
+            };
+
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&removed).unwrap());
+                return Ok(());
+            }
+
+            for path in &removed {
+                println!("- {}", path.display());
+            }
+
+            match dry_run {
+                true => println!("{} untracked entries would be removed.", removed.len()),
+                false => println!("Removed {} untracked entries.", removed.len()),
+            }
+
+            Ok(())
+        }
+        Commands::Merge {
+            projects,
+            output_path,
+        } => {
+            let mut manifests = projects
+                .iter()
+                .map(ProjectManifest::read_from_file)
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter();
+
+            let mut merged = manifests
+                .next()
+                .expect("clap requires at least two projects");
+            for manifest in manifests {
+                merged.dependencies.merge(&manifest.dependencies);
+            }
+
+            fs::write(&output_path, toml::to_string_pretty(&merged).unwrap())
+                .map_fs_error(&output_path)?;
+
+            if json_output {
+                let output = serde_json::json!({
+                    "projects": projects,
+                    "output": output_path,
+                    "dependencies": merged.dependencies.dependencies.len(),
+                });
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                return Ok(());
+            }
+
+            println!(
+                "Merged the dependencies of {} projects into {}.",
+                projects.len(),
+                output_path.display()
+            );
+
+            Ok(())
+        }
+        Commands::Audit {
+            json,
+            cache_dir,
+            project_path,
+        } => {
+            ts::init_repository("https://thunderstore.io", None);
+
+            let manifest = ProjectManifest::read_from_file(&project_path)?;
+            let dependencies = manifest.dependencies.dependencies;
+            let project_path = ProjectPath::new(&project_path)?;
+
+            let resolved = match cache_dir {
+                Some(cache_dir) => {
+                    PackageResolver::resolve_with_cache(
+                        dependencies.clone(),
+                        &project_path,
+                        &cache_dir,
+                    )
+                    .await?
+                }
+                None => PackageResolver::resolve_new(dependencies.clone(), &project_path).await?,
+            };
+
+            let conflicts = resolved.conflicts(&dependencies);
+
+            if json || json_output {
+                println!("{}", serde_json::to_string_pretty(&conflicts).unwrap());
+            } else if conflicts.is_empty() {
+                println!("No dependency conflicts found.");
+            } else {
+                for conflict in &conflicts {
+                    println!(
+                        "{} {} is pinned to {} but {} requires {}",
+                        "[!]".bright_yellow(),
+                        conflict.package.bold(),
+                        conflict.pinned,
+                        conflict.required_by,
+                        conflict.required
+                    );
+                }
+            }
+
+            match conflicts.len() {
+                0 => Ok(()),
+                count => Err(Error::AuditFailed(count)),
+            }
+        }
+        Commands::Outdated {
+            all,
+            json,
+            project_path,
+        } => {
+            ts::init_repository("https://thunderstore.io", None);
+
+            let project_path = ProjectPath::new(&project_path)?;
+            let manifest =
+                ProjectManifest::read_from_file(project_path.path().join("Thunderstore.toml"))?;
+            let lock = LockFile::open_or_new(&project_path.path().join("Thunderstore.lock"))?;
+
+            let direct = manifest
+                .dependencies
+                .dependencies
+                .iter()
+                .map(|x| x.to_loose_ident_string())
+                .collect::<HashSet<_>>();
+            let installed = lock
+                .packages
+                .into_iter()
+                .filter(|(loose_ident, _)| all || direct.contains(loose_ident))
+                .map(|(_, package)| package.identifier)
+                .collect::<Vec<_>>();
+
+            let versions = package::outdated::latest_versions(&installed).await?;
+
+            if json || json_output {
+                println!("{}", serde_json::to_string_pretty(&versions).unwrap());
+                return Ok(());
+            }
+
+            let width = versions.iter().map(|x| x.package.len()).max().unwrap_or(0);
+            println!("{:width$}  {:>10}  {:>10}", "Package", "Current", "Latest");
+
+            for version in &versions {
+                let latest = format!("{:>10}", version.latest.to_string());
+                let latest = match version.is_outdated() {
+                    true => latest.bright_green(),
+                    false => latest.normal(),
+                };
+
+                println!(
+                    "{:width$}  {:>10}  {latest}",
+                    version.package,
+                    version.current.to_string(),
+                );
+            }
+
+            let outdated = versions.iter().filter(|x| x.is_outdated()).count();
+            println!("\n{outdated} of {} packages are outdated.", versions.len());
+
+            Ok(())
+        }
+        Commands::Resolve {
+            json,
+            explain,
+            cache_dir,
             project_path,
         } => {
             ts::init_repository("https://thunderstore.io", None);
 
-            let reporter = Box::new(IndicatifReporter);
+            let manifest = ProjectManifest::read_from_file(&project_path)?;
+            let dependencies = manifest.dependencies.dependencies;
             let project_path = ProjectPath::new(&project_path)?;
 
-            let packages = PackageResolver::resolve_new(packages, &project_path).await?;
-            packages.apply(reporter).await?;
+            let resolved = match cache_dir {
+                Some(cache_dir) => {
+                    PackageResolver::resolve_with_cache(
+                        dependencies.clone(),
+                        &project_path,
+                        &cache_dir,
+                    )
+                    .await?
+                }
+                None => PackageResolver::resolve_new(dependencies.clone(), &project_path).await?,
+            };
+
+            if explain {
+                let explanations = resolved.explain(&dependencies);
+
+                if json || json_output {
+                    println!("{}", serde_json::to_string_pretty(&explanations).unwrap());
+                } else {
+                    for explanation in explanations {
+                        println!("{explanation}");
+                    }
+                }
+
+                return Ok(());
+            }
+
+            let idents = resolved
+                .packages_to_install
+                .iter()
+                .map(|package| &package.identifier)
+                .sorted()
+                .map(|ident| ident.to_string())
+                .collect::<Vec<_>>();
+
+            if json || json_output {
+                println!("{}", serde_json::to_string_pretty(&idents).unwrap());
+            } else {
+                for ident in idents {
+                    println!("{ident}");
+                }
+            }
 
             Ok(())
         }
         Commands::ImportGame {
             game_id,
+            from_file,
             custom_id,
             custom_name,
             exe_path,
+            steam_dir,
+            save_steam_dir,
+            platform_priority,
+            select,
+            first,
+            yes,
             tcli_directory,
             repository,
             project_path,
         } => {
+            // Imports report each game as they go and may prompt to replace existing entries.
+            if json_output {
+                return Err(Error::JsonOutputUnsupported("import-game"));
+            }
+
             ts::init_repository("https://thunderstore.io", None);
 
             let project_path = ProjectPath::new(&project_path)?;
+            let config = Config::load(project_path.path())?;
+
+            if let Some(steam_dir) = steam_dir.as_ref().filter(|_| save_steam_dir) {
+                let steam_dir = steam_dir.canonicalize().map_fs_error(steam_dir)?;
+                Config::set_user_value(
+                    ConfigKey::SteamDir.as_str(),
+                    steam_dir.to_string_lossy().into_owned(),
+                )?;
+            }
+
+            let entries = match &from_file {
+                Some(path) => import_list::read(path)?,
+                None => {
+                    let query = game_id.expect("Clap requires a game id without --from-file");
+                    let schema = ecosystem::get_schema(config.schema_channel).await?;
+
+                    match import::select_game(&schema, &query, select, first)? {
+                        Some(game_id) => vec![Ok(ImportListEntry {
+                            line: 1,
+                            game_id,
+                            platform: None,
+                        })],
+                        None => return Ok(()),
+                    }
+                }
+            };
+
+            let options = ImportOptions {
+                custom_id,
+                custom_name,
+                exe_path,
+                steam_dir: steam_dir.or(config.steam_dir),
+                platform_priority,
+                yes,
+            };
+
+            import::import_games(
+                &project_path,
+                config.schema_channel,
+                entries,
+                &options,
+                from_file.is_some(),
+            )
+            .await
+        }
+        Commands::RemoveGame {
+            game_id,
+            project_path,
+        } => {
+            let project_path = ProjectPath::new(&project_path)?;
+            let removed = registry::remove_data(&project_path, &game_id)?;
 
-            GameImportBuilder::new(&game_id)
-                .await?
-                .with_custom_id(custom_id)
-                .with_custom_name(custom_name)
-                .with_custom_exe(exe_path)
-                .import(&project_path)
+            match (json_output, removed) {
+                (true, _) => {
+                    let output = serde_json::json!({ "game_id": game_id, "removed": removed });
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                }
+                (false, true) => println!("Removed {game_id} from the project."),
+                (false, false) => println!("No such imported game: {game_id}."),
+            }
+
+            Ok(())
         }
-        Commands::UpdateSchema {} => {
+        Commands::Games { command } => match command {
+            GamesSubcommand::SetExe {
+                game_id,
+                exe_path,
+                auto,
+                project_path,
+            } => {
+                let project_path = ProjectPath::new(&project_path)?;
+                let mut data = registry::get_game_data(&project_path, &game_id)
+                    .ok_or_else(|| Error::GameNotImported(game_id.clone()))?;
+
+                let exe_path = match (exe_path, auto) {
+                    (Some(exe_path), false) => exe_path,
+                    (None, true) => {
+                        let config = Config::load(project_path.path())?;
+                        registry::detect_exe(&data, config.schema_channel).await?
+                    }
+                    _ => unreachable!("Clap requires either an exe path or --auto"),
+                };
+
+                if !exe_path.is_file() {
+                    return Err(Error::FileNotFound(exe_path));
+                }
+
+                data.active_distribution.exe_path =
+                    exe_path.canonicalize().map_fs_error(&exe_path)?;
+
+                if json_output {
+                    let output = serde_json::json!({
+                        "game_id": data.identifier,
+                        "exe_path": data.active_distribution.exe_path,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                } else {
+                    println!(
+                        "Set the executable of '{}' to {}",
+                        data.identifier,
+                        data.active_distribution.exe_path.display()
+                    );
+                }
+
+                registry::write_data(&project_path, data)
+            }
+        },
+        Commands::UpdateSchema { schema_channel } => {
             ts::init_repository("https://thunderstore.io", None);
 
-            if !ecosystem::schema_exists() {
-                let new = ecosystem::get_schema().await?;
-                println!(
-                    "Downloaded the latest ecosystem schema, version {}",
-                    new.schema_version
-                );
+            let config = Config::load(Path::new("./"))?;
+            let schema_channel = schema_channel.map_or(config.schema_channel, SchemaChannel::from);
 
+            let (current, new) = ecosystem::update_schema(schema_channel).await?;
+
+            if json_output {
+                let output = serde_json::json!({
+                    "channel": schema_channel.name(),
+                    "previous_version": current.map(|x| x.schema_version.to_string()),
+                    "version": new.schema_version.to_string(),
+                });
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
                 return Ok(());
             }
 
-            let current = ecosystem::get_schema().await?;
-            ecosystem::remove_schema()?;
-            let new = ecosystem::get_schema().await?;
-
-            if current.schema_version == new.schema_version {
-                println!(
+            match (current, new) {
+                (None, new) => println!(
+                    "Downloaded the latest ecosystem schema, version {}",
+                    new.schema_version
+                ),
+                (Some(current), new) if current.schema_version == new.schema_version => println!(
                     "The local ecosystem schema is the latest, version {}",
                     new.schema_version
-                );
-            } else {
-                println!(
+                ),
+                (Some(current), new) => println!(
                     "Updated ecosystem schema from version {} to {}",
                     current.schema_version, new.schema_version
-                );
+                ),
             }
 
             Ok(())
@@ -182,6 +723,11 @@ async fn main() -> Result<(), Error> {
             ListSubcommand::Platforms { target, detected } => {
                 let platforms = registry::get_supported_platforms(&target);
 
+                if json_output {
+                    println!("{}", serde_json::to_string_pretty(&platforms).unwrap());
+                    return Ok(());
+                }
+
                 println!("TCLI supports the following platforms on {target}");
                 for plat in platforms {
                     println!("- {plat}");
@@ -193,6 +739,11 @@ async fn main() -> Result<(), Error> {
                 let project_path = ProjectPath::new(&project_path)?;
                 let games = registry::get_registry(&project_path)?;
 
+                if json_output {
+                    println!("{}", serde_json::to_string_pretty(&games).unwrap());
+                    return Ok(());
+                }
+
                 for game in games {
                     println!("{game:#?}");
                 }
@@ -200,7 +751,8 @@ async fn main() -> Result<(), Error> {
                 Ok(())
             }
             ListSubcommand::SupportedGames { search } => {
-                let schema = ecosystem::get_schema().await?;
+                let config = Config::load(Path::new("./"))?;
+                let schema = ecosystem::get_schema(config.schema_channel).await?;
                 let pattern = WildMatch::new(&search);
 
                 let filtered = schema
@@ -212,6 +764,21 @@ async fn main() -> Result<(), Error> {
                     })
                     .collect::<Vec<_>>();
 
+                if json_output {
+                    let games = filtered
+                        .iter()
+                        .map(|(_, game_def)| {
+                            serde_json::json!({
+                                "label": game_def.label,
+                                "display_name": game_def.meta.display_name,
+                                "uuid": game_def.uuid,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    println!("{}", serde_json::to_string_pretty(&games).unwrap());
+                    return Ok(());
+                }
+
                 for (_, game_def) in filtered.iter() {
                     println!("{}", game_def.meta.display_name);
                     println!("- label: {}", game_def.label);
@@ -223,20 +790,305 @@ async fn main() -> Result<(), Error> {
 
                 Ok(())
             },
-            ListSubcommand::InstalledMods { project_path } => {
+            ListSubcommand::InstalledMods {
+                by_namespace,
+                project_path,
+            } => {
                 let path = ProjectPath::new(&project_path)?.path().join("Thunderstore.lock");
                 let lock = LockFile::open_or_new(&path)?;
 
+                if json_output {
+                    let packages = lock
+                        .packages
+                        .values()
+                        .map(|package| &package.identifier)
+                        .sorted()
+                        .map(|ident| {
+                            serde_json::json!({
+                                "namespace": ident.namespace,
+                                "name": ident.name,
+                                "version": ident.version.to_string(),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    println!("{}", serde_json::to_string_pretty(&packages).unwrap());
+                    return Ok(());
+                }
+
                 println!("Installed packages:");
 
-                for (_, package) in lock.packages {
+                if !by_namespace {
+                    for package in lock
+                        .packages
+                        .values()
+                        .sorted_by(|a, b| a.identifier.cmp(&b.identifier))
+                    {
+                        println!(
+                            "- {}-{} ({})",
+                            package.identifier.namespace.bold(),
+                            package.identifier.name.bold(),
+                            package.identifier.version.to_string().truecolor(90, 90, 90)
+                        );
+                    }
+
+                    return Ok(());
+                }
+
+                let namespaces = lock
+                    .packages
+                    .values()
+                    .map(|package| &package.identifier)
+                    .into_group_map_by(|ident| ident.namespace.as_str());
+
+                // Package names which are installed under more than one namespace.
+                let shared_names = lock
+                    .packages
+                    .values()
+                    .map(|package| &package.identifier)
+                    .into_group_map_by(|ident| ident.name.as_str())
+                    .into_iter()
+                    .filter(|(_, idents)| idents.len() > 1)
+                    .map(|(name, _)| name)
+                    .collect::<HashSet<_>>();
+
+                for (namespace, idents) in namespaces.into_iter().sorted_by_key(|(x, _)| *x) {
+                    println!("{}", namespace.bold());
+
+                    for ident in idents.into_iter().sorted() {
+                        let marker = if shared_names.contains(ident.name.as_str()) {
+                            " [!]".bright_yellow()
+                        } else {
+                            "".normal()
+                        };
+
+                        println!(
+                            "  - {} ({}){}",
+                            ident.name.bold(),
+                            ident.version.to_string().truecolor(90, 90, 90),
+                            marker
+                        );
+                    }
+                }
+
+                if !shared_names.is_empty() {
+                    println!(
+                        "\n{} the following package names exist under multiple namespaces: {}",
+                        "[!]".bright_yellow(),
+                        shared_names.iter().sorted().join(", ")
+                    );
+                }
+
+                Ok(())
+            }
+        },
+        Commands::Package { command } => match command {
+            PackageSubcommand::Info { package, json } => {
+                ts::init_repository("https://thunderstore.io", None);
+
+                let info = package::info::get_info(&package).await?;
+
+                if json || json_output {
+                    println!("{}", serde_json::to_string_pretty(&info).unwrap());
+                } else {
+                    println!("{info}");
+                }
+
+                Ok(())
+            }
+        },
+        Commands::Cache { command } => match command {
+            CacheSubcommand::Verify { cache_dir } => {
+                let cache_dir = cache_dir.as_deref().unwrap_or(cache::default_cache_dir());
+                let removed = cache::remove_incomplete(cache_dir)?;
+
+                if json_output {
+                    println!("{}", serde_json::to_string_pretty(&removed).unwrap());
+                    return Ok(());
+                }
+
+                for path in &removed {
+                    println!("- {}", path.display());
+                }
+                println!(
+                    "Removed {} incomplete cache entries from {}, they will be downloaded again when next needed.",
+                    removed.len(),
+                    cache_dir.display()
+                );
+
+                Ok(())
+            }
+            CacheSubcommand::Clean {
+                orphans,
+                yes,
+                cache_dir,
+                project_path,
+            } => {
+                let cache_dir = cache_dir.as_deref().unwrap_or(cache::default_cache_dir());
+
+                let projects = match orphans {
+                    // Projects which no longer exist don't reference anything.
+                    true => known::read()?
+                        .into_iter()
+                        .filter(|x| x.exists())
+                        .map(|x| x.path)
+                        .collect(),
+                    false => vec![ProjectPath::new(&project_path)?.path().to_path_buf()],
+                };
+
+                let mut referenced = HashSet::new();
+                for project in &projects {
+                    let lock = LockFile::open_or_new(&project.join("Thunderstore.lock"))?;
+                    referenced.extend(lock.packages.into_values().map(|x| x.identifier));
+                }
+
+                let question = format!(
+                    "Remove every cached package not installed within {}?",
+                    match orphans {
+                        true => format!("any of {} known projects", projects.len()),
+                        false => projects[0].display().to_string(),
+                    }
+                );
+                if !yes && !prompt::confirm(&question)? {
+                    return Ok(());
+                }
+
+                let removed = cache::remove_unreferenced(cache_dir, &referenced)?;
+
+                if json_output {
+                    println!("{}", serde_json::to_string_pretty(&removed).unwrap());
+                    return Ok(());
+                }
+
+                for path in &removed {
+                    println!("- {}", path.display());
+                }
+                println!(
+                    "Removed {} packages from {}.",
+                    removed.len(),
+                    cache_dir.display()
+                );
+
+                Ok(())
+            }
+            CacheSubcommand::Path {
+                package,
+                json,
+                cache_dir,
+            } => {
+                let cache_dir = cache_dir.as_deref().unwrap_or(cache::default_cache_dir());
+
+                if !cache::is_complete(cache_dir, &package) {
+                    return Err(Error::PackageNotCached(package.to_string()));
+                }
+
+                let path = cache::get_cache_location(cache_dir, &package);
+
+                if json || json_output {
+                    let output = serde_json::json!({
+                        "package": package.to_string(),
+                        "path": path,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                } else {
+                    println!("{}", path.display());
+                }
+
+                Ok(())
+            }
+        },
+        Commands::Projects { command } => match command {
+            ProjectsSubcommand::List { json } => {
+                let projects = known::read()?
+                    .into_iter()
+                    .sorted_by_key(|x| std::cmp::Reverse(x.last_accessed))
+                    .collect::<Vec<_>>();
+
+                if json || json_output {
+                    println!("{}", serde_json::to_string_pretty(&projects).unwrap());
+                    return Ok(());
+                }
+
+                for project in &projects {
+                    let last_accessed = project
+                        .last_accessed()
+                        .map_or_else(|| "unknown".to_string(), |x| x.date().to_string());
+                    let missing = match project.exists() {
+                        true => "".normal(),
+                        false => " (missing)".bright_red(),
+                    };
+
                     println!(
-                        "- {}-{} ({})",
-                        package.identifier.namespace.bold(),
-                        package.identifier.name.bold(),
-                        package.identifier.version.to_string().truecolor(90, 90, 90)
+                        "{} last used {last_accessed}{missing}",
+                        project.path.display()
                     );
                 }
+                println!("\n{} projects are known.", projects.len());
+
+                Ok(())
+            }
+            ProjectsSubcommand::Forget { path, stale } => {
+                if stale {
+                    let forgotten = known::forget_stale()?;
+
+                    if json_output {
+                        println!("{}", serde_json::to_string_pretty(&forgotten).unwrap());
+                        return Ok(());
+                    }
+
+                    for path in &forgotten {
+                        println!("- {}", path.display());
+                    }
+                    println!("Forgot {} projects which no longer exist.", forgotten.len());
+                } else {
+                    let path = path.expect("Clap requires a path without --stale");
+                    let forgotten = known::forget(&path)?;
+
+                    match (json_output, forgotten) {
+                        (true, _) => {
+                            let forgotten = match forgotten {
+                                true => vec![path],
+                                false => vec![],
+                            };
+                            println!("{}", serde_json::to_string_pretty(&forgotten).unwrap());
+                        }
+                        (false, true) => println!("Forgot {}.", path.display()),
+                        (false, false) => println!("{} is not a known project.", path.display()),
+                    }
+                }
+
+                Ok(())
+            }
+        },
+        Commands::Config { command } => match command {
+            ConfigSubcommand::Set { key, value } => {
+                let value = match key {
+                    ConfigKey::SteamDir => {
+                        let steam_dir =
+                            PathBuf::from(&value).canonicalize().map_fs_error(&value)?;
+                        toml::Value::from(steam_dir.to_string_lossy().into_owned())
+                    }
+                    ConfigKey::SchemaChannel => {
+                        let channel = SchemaChannelArg::from_str(&value, true)
+                            .map_err(|e| Error::InvalidConfig(Box::new(e.into())))?;
+                        let channel = channel.to_possible_value().unwrap();
+                        toml::Value::from(channel.get_name())
+                    }
+                    ConfigKey::BlockNsfw => {
+                        let block_nsfw = value
+                            .parse::<bool>()
+                            .map_err(|e| Error::InvalidConfig(Box::new(e.to_string().into())))?;
+                        toml::Value::from(block_nsfw)
+                    }
+                };
+
+                Config::set_user_value(key.as_str(), value.clone())?;
+
+                if json_output {
+                    let output = serde_json::json!({ "key": key.as_str(), "value": value });
+                    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+                } else {
+                    println!("Set {} in the user config.", key.as_str());
+                }
 
                 Ok(())
             }
@@ -1,23 +1,215 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use once_cell::sync::Lazy;
 
 use crate::error::IoResultToTcli;
+use crate::ts::package_manifest::PackageManifestV1;
 use crate::ts::package_reference::PackageReference;
+use crate::ts::version::Version;
 use crate::util::TempFile;
 use crate::{Error, TCLI_HOME};
 
 static CACHE_LOCATION: Lazy<PathBuf> = Lazy::new(|| TCLI_HOME.join("package_cache"));
 
+/// The default package cache directory within TCLI_HOME.
+pub fn default_cache_dir() -> &'static Path {
+    CACHE_LOCATION.as_path()
+}
+
 pub async fn get_temp_zip_file(
+    cache_dir: &Path,
     package: &PackageReference,
 ) -> Result<TempFile<tokio::fs::File>, Error> {
-    fs::create_dir_all(CACHE_LOCATION.as_path()).map_fs_error(CACHE_LOCATION.as_path())?;
-    let path = CACHE_LOCATION.join(format!("{package}.zip.tmp"));
+    fs::create_dir_all(cache_dir).map_fs_error(cache_dir)?;
+    let path = cache_dir.join(format!("{package}.zip.tmp"));
     Ok(TempFile::open_async(path).await?)
 }
 
-pub fn get_cache_location(package: &PackageReference) -> PathBuf {
-    CACHE_LOCATION.join(package.to_string())
+pub fn get_cache_location(cache_dir: &Path, package: &PackageReference) -> PathBuf {
+    cache_dir.join(package.to_string())
+}
+
+/// Check that a package within the cache was extracted completely, meaning it contains a
+/// readable manifest which doesn't name a different version than the package is cached under.
+///
+/// Manifests which can't be parsed still count as complete, as some published packages have
+/// them. [`Package::from_cache`](super::Package::from_cache) falls back to the repository for
+/// their dependencies.
+pub fn is_complete(cache_dir: &Path, package: &PackageReference) -> bool {
+    let manifest_path = get_cache_location(cache_dir, package).join("manifest.json");
+
+    let Ok(manifest) = fs::read_to_string(manifest_path) else {
+        return false;
+    };
+
+    serde_json::from_str::<PackageManifestV1>(manifest.trim_start_matches('\u{feff}'))
+        .map_or(true, |manifest| manifest.version == package.version)
+}
+
+/// List the versions of a package which are completely extracted within the cache, in ascending
+/// order.
+pub fn cached_versions(cache_dir: &Path, package: &PackageReference) -> Vec<Version> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return vec![];
+    };
+
+    let mut versions = entries
+        .filter_map(|entry| {
+            entry
+                .ok()?
+                .file_name()
+                .to_str()?
+                .parse::<PackageReference>()
+                .ok()
+        })
+        .filter(|x| x.to_loose_ident_string() == package.to_loose_ident_string())
+        .filter(|x| is_complete(cache_dir, x))
+        .map(|x| x.version)
+        .collect::<Vec<_>>();
+
+    versions.sort();
+    versions
+}
+
+/// Remove every cached package which isn't within the referenced set of package identifiers,
+/// returning the paths which were removed.
+pub fn remove_unreferenced(
+    cache_dir: &Path,
+    referenced: &HashSet<PackageReference>,
+) -> Result<Vec<PathBuf>, Error> {
+    if !cache_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut removed = Vec::new();
+
+    for entry in fs::read_dir(cache_dir).map_fs_error(cache_dir)? {
+        let path = entry.map_fs_error(cache_dir)?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        let Ok(package) = file_name.parse::<PackageReference>() else {
+            continue;
+        };
+
+        if path.is_dir() && !referenced.contains(&package) {
+            fs::remove_dir_all(&path).map_fs_error(&path)?;
+            removed.push(path);
+        }
+    }
+
+    removed.sort();
+    Ok(removed)
+}
+
+/// Remove incomplete packages and leftover partial downloads from the cache, returning the
+/// paths which were removed. Removed packages are downloaded again the next time they're needed.
+pub fn remove_incomplete(cache_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !cache_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut removed = Vec::new();
+
+    for entry in fs::read_dir(cache_dir).map_fs_error(cache_dir)? {
+        let path = entry.map_fs_error(cache_dir)?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        if path.is_file() && file_name.ends_with(".zip.tmp") {
+            fs::remove_file(&path).map_fs_error(&path)?;
+            removed.push(path);
+            continue;
+        }
+
+        let Ok(package) = file_name.parse::<PackageReference>() else {
+            continue;
+        };
+
+        if path.is_dir() && !is_complete(cache_dir, &package) {
+            fs::remove_dir_all(&path).map_fs_error(&path)?;
+            removed.push(path);
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a cached package whose manifest names the given version.
+    fn write_package(cache_dir: &Path, package: &str, version: &str) -> PathBuf {
+        let path = cache_dir.join(package);
+        fs::create_dir_all(&path).unwrap();
+        let manifest = serde_json::json!({
+            "name": "Mod",
+            "description": "",
+            "version_number": version,
+            "dependencies": [],
+            "website_url": "",
+        });
+        fs::write(path.join("manifest.json"), manifest.to_string()).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn remove_incomplete_keeps_complete_packages() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let complete = write_package(cache_dir.path(), "Author-Mod-1.0.0", "1.0.0");
+
+        let missing_manifest = cache_dir.path().join("Author-Other-1.0.0");
+        fs::create_dir(&missing_manifest).unwrap();
+        let wrong_version = write_package(cache_dir.path(), "Author-Mod-2.0.0", "1.0.0");
+        let partial_download = cache_dir.path().join("Author-Mod-3.0.0.zip.tmp");
+        fs::write(&partial_download, "").unwrap();
+
+        let mut removed = remove_incomplete(cache_dir.path()).unwrap();
+        removed.sort();
+
+        assert_eq!(removed, [wrong_version, partial_download, missing_manifest]);
+        assert!(complete.join("manifest.json").is_file());
+    }
+
+    #[test]
+    fn unparsable_manifest_is_complete() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let path = cache_dir.path().join("Author-Mod-1.0.0");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("manifest.json"), "not json").unwrap();
+
+        let package = "Author-Mod-1.0.0".parse().unwrap();
+        assert!(is_complete(cache_dir.path(), &package));
+    }
+
+    #[test]
+    fn remove_unreferenced_keeps_referenced_packages() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let referenced = write_package(cache_dir.path(), "Author-Mod-1.0.0", "1.0.0");
+        let unreferenced = write_package(cache_dir.path(), "Author-Mod-2.0.0", "2.0.0");
+
+        let references = HashSet::from(["Author-Mod-1.0.0".parse().unwrap()]);
+        let removed = remove_unreferenced(cache_dir.path(), &references).unwrap();
+
+        assert_eq!(removed, [unreferenced]);
+        assert!(referenced.is_dir());
+    }
+
+    #[test]
+    fn cached_versions_lists_complete_versions_in_order() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        write_package(cache_dir.path(), "Author-Mod-1.10.0", "1.10.0");
+        write_package(cache_dir.path(), "Author-Mod-1.2.0", "1.2.0");
+        write_package(cache_dir.path(), "Author-Other-1.5.0", "1.5.0");
+        fs::create_dir(cache_dir.path().join("Author-Mod-1.3.0")).unwrap();
+
+        let package = "Author-Mod-1.0.0".parse().unwrap();
+
+        assert_eq!(
+            cached_versions(cache_dir.path(), &package),
+            [Version::new(1, 2, 0), Version::new(1, 10, 0)]
+        );
+    }
 }
@@ -0,0 +1,130 @@
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::ts::experimental::package;
+use crate::ts::package_reference::{LoosePackageReference, PackageReference};
+use crate::ts::version::Version;
+
+/// Metadata describing a package within the repository, gathered without installing it.
+#[derive(Serialize, Debug)]
+pub struct PackageInfo {
+    pub package: String,
+    pub version: Version,
+    pub description: String,
+    pub website_url: String,
+    pub file_size: Option<u64>,
+    pub dependencies: Vec<String>,
+    pub listing: Option<ListingInfo>,
+}
+
+/// Listing metadata of the package as a whole, rather than of a single version.
+#[derive(Serialize, Debug)]
+pub struct ListingInfo {
+    pub latest_version: Version,
+    pub total_downloads: u32,
+    pub rating_score: u32,
+    pub is_deprecated: bool,
+    pub date_updated: String,
+    pub communities: Vec<String>,
+    pub has_nsfw_content: bool,
+}
+
+/// Look up a package within the repository. The latest version is described when the reference
+/// doesn't specify one.
+///
+/// Listing metadata is best-effort when a version is given: the version's own metadata is still
+/// returned if the listing can't be fetched.
+pub async fn get_info(reference: &LoosePackageReference) -> Result<PackageInfo, Error> {
+    let LoosePackageReference {
+        namespace, name, ..
+    } = reference;
+
+    let (version, listing) = match reference.version {
+        Some(version) => {
+            let (version, listing) = futures::join!(
+                package::get_version_metadata(namespace, name, version),
+                package::get_metadata(namespace, name),
+            );
+            (version?, listing.ok())
+        }
+        None => {
+            let listing = package::get_metadata(namespace, name).await?;
+            (listing.latest.clone(), Some(listing))
+        }
+    };
+
+    let file_size = package::get_file_size(&version.download_url)
+        .await
+        .ok()
+        .flatten();
+
+    let listing = listing.map(|listing| ListingInfo {
+        latest_version: listing.latest.version,
+        total_downloads: listing.total_downloads,
+        rating_score: listing.rating_score,
+        is_deprecated: listing.is_deprecated,
+        date_updated: listing.date_updated,
+        has_nsfw_content: listing
+            .community_listings
+            .iter()
+            .any(|x| x.has_nsfw_content),
+        communities: listing
+            .community_listings
+            .into_iter()
+            .map(|x| x.community)
+            .collect(),
+    });
+
+    Ok(PackageInfo {
+        package: format!("{namespace}-{name}"),
+        version: version.version,
+        description: version.description,
+        website_url: version.website_url,
+        file_size,
+        dependencies: version
+            .dependencies
+            .iter()
+            .map(PackageReference::to_string)
+            .collect(),
+        listing,
+    })
+}
+
+impl Display for PackageInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}-{}", self.package, self.version)?;
+        writeln!(f, "{}", self.description)?;
+        writeln!(f)?;
+        writeln!(f, "Website:      {}", self.website_url)?;
+
+        match self.file_size {
+            Some(size) => writeln!(f, "File size:    {size} bytes")?,
+            None => writeln!(f, "File size:    unknown")?,
+        }
+
+        if let Some(listing) = &self.listing {
+            writeln!(f, "Latest:       {}", listing.latest_version)?;
+            writeln!(f, "Downloads:    {}", listing.total_downloads)?;
+            writeln!(f, "Rating:       {}", listing.rating_score)?;
+            writeln!(f, "Updated:      {}", listing.date_updated)?;
+            writeln!(f, "Communities:  {}", listing.communities.join(", "))?;
+            writeln!(f, "Deprecated:   {}", listing.is_deprecated)?;
+            writeln!(f, "NSFW:         {}", listing.has_nsfw_content)?;
+        }
+
+        match self.dependencies.is_empty() {
+            true => write!(f, "Dependencies: none"),
+            false => write!(
+                f,
+                "Dependencies:\n{}",
+                self.dependencies
+                    .iter()
+                    .map(|x| format!("- {x}"))
+                    .join("\n")
+            ),
+        }
+    }
+}
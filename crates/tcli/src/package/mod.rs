@@ -1,8 +1,11 @@
-mod cache;
+pub mod cache;
+pub mod info;
+pub mod outdated;
+pub mod report;
 pub mod resolver;
 
 use std::io::{ErrorKind, Read, Seek};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use colored::Colorize;
 use futures::prelude::*;
@@ -12,13 +15,16 @@ use serde_with::{self, serde_as, DisplayFromStr};
 use tokio::fs;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+use self::report::InstallReport;
 use crate::error::{Error, IoResultToTcli};
 use crate::project::ProjectPath;
 use crate::ts::experimental::package;
 use crate::ts::package_manifest::PackageManifestV1;
 use crate::ts::package_reference::PackageReference;
-use crate::ts::CLIENT;
+use crate::ts::{SendTraced, CLIENT};
 use crate::ui::reporter::ProgressBarTrait;
+use crate::util::path::long_path;
+use crate::util::TempFile;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum PackageSource {
@@ -38,16 +44,17 @@ pub struct Package {
 }
 
 impl Package {
-    pub async fn resolve_new(ident: PackageReference) -> Result<Self, Error> {
-        if cache::get_cache_location(&ident).exists() {
-            return Package::from_cache(ident).await;
+    /// Resolve the package, preferring a copy already present within the given cache directory.
+    pub async fn resolve_new(ident: PackageReference, cache_dir: &Path) -> Result<Self, Error> {
+        if cache::is_complete(cache_dir, &ident) {
+            return Package::from_cache(ident, cache_dir).await;
         }
 
         Package::from_repo(ident).await
     }
 
-    pub async fn from_cache(ident: PackageReference) -> Result<Self, Error> {
-        let path = cache::get_cache_location(&ident);
+    pub async fn from_cache(ident: PackageReference, cache_dir: &Path) -> Result<Self, Error> {
+        let path = cache::get_cache_location(cache_dir, &ident);
         let manifest_path = path.join("manifest.json");
 
         let mut manifest_str = String::new();
@@ -68,7 +75,7 @@ impl Package {
                 dependencies: manifest.dependencies,
             }),
             Err(_) => {
-                println!(
+                eprintln!(
                     "{} package \"{}\" has a malformed manifest, grabbing info from repo instead",
                     "[!]".bright_yellow(),
                     ident,
@@ -96,20 +103,32 @@ impl Package {
     pub async fn add(
         &self,
         project: &ProjectPath,
+        cache_dir: &Path,
         reporter: Box<dyn ProgressBarTrait>,
-    ) -> Result<(), Error> {
+    ) -> Result<InstallReport, Error> {
+        let mut report = InstallReport {
+            packages: 1,
+            ..Default::default()
+        };
+
         let cache_path = match &self.source {
             PackageSource::Local(path) => add_to_cache(
+                cache_dir,
                 &self.identifier,
                 std::fs::File::open(path).map_fs_error(path)?,
             )?,
-            PackageSource::Remote(_) => self.download(reporter.as_ref()).await?,
+            PackageSource::Remote(_) => {
+                let (cache_path, downloaded) = self.download(cache_dir, reporter.as_ref()).await?;
+                report.downloaded_bytes = downloaded;
+                cache_path
+            }
             PackageSource::Cache(path) => path.clone(),
         };
+        let cache_path = long_path(&cache_path);
 
         let project_state = project.path().join("project_state");
 
-        let install_dir = project_state.join(self.identifier.to_string());
+        let install_dir = long_path(&project_state.join(self.identifier.to_string()));
 
         if install_dir.is_dir() {
             fs::remove_dir_all(&install_dir)
@@ -127,9 +146,10 @@ impl Package {
                     .await
                     .map_fs_error(&dest_path)?;
             } else if item.file_type().is_file() {
-                tokio::fs::copy(item.path(), &dest_path)
+                report.installed_bytes += tokio::fs::copy(item.path(), &dest_path)
                     .await
                     .map_fs_error(&dest_path)?;
+                report.installed_files += 1;
             }
         }
 
@@ -144,24 +164,26 @@ impl Package {
         reporter.println(&finished_msg);
         reporter.finish_and_clear();
 
-        Ok(())
+        Ok(report)
     }
 
-    async fn download(&self, reporter: &dyn ProgressBarTrait) -> Result<PathBuf, Error> {
+    async fn download(
+        &self,
+        cache_dir: &Path,
+        reporter: &dyn ProgressBarTrait,
+    ) -> Result<(PathBuf, u64), Error> {
         let PackageSource::Remote(package_source) = &self.source else {
             panic!("Invalid use, this is a local package.")
         };
 
-        let output_path = cache::get_cache_location(&self.identifier);
+        let output_path = cache::get_cache_location(cache_dir, &self.identifier);
 
-        if output_path.is_dir() {
+        // An incomplete package left in the cache is replaced by the fresh download.
+        if cache::is_complete(cache_dir, &self.identifier) {
             reporter.finish();
-            return Ok(output_path);
+            return Ok((output_path, 0));
         }
 
-        let download_result = CLIENT.get(package_source).send().await.unwrap();
-        let download_size = download_result.content_length().unwrap();
-
         let progress_message = format!(
             "{}-{} ({})",
             self.identifier.namespace.bold(),
@@ -169,33 +191,81 @@ impl Package {
             self.identifier.version.to_string().truecolor(90, 90, 90)
         );
 
-        reporter.set_length(download_size);
         reporter.set_message(format!("Downloading {progress_message}..."));
 
+        // An incomplete download is retried once, the temp file of the failed attempt is removed
+        // when it's dropped so nothing corrupt is ever cached.
+        let mut retried = false;
+        let (temp_file, downloaded) = loop {
+            match self.fetch(package_source, cache_dir, reporter).await {
+                Err(Error::CorruptDownload { .. } | Error::TruncatedResponse(_)) if !retried => {
+                    retried = true;
+                }
+                result => break result?,
+            }
+        };
+
+        reporter.set_message(format!("Unzipping {progress_message}..."));
+
+        let cache_path = add_to_cache(
+            cache_dir,
+            &self.identifier,
+            temp_file.into_std().await.file(),
+        )?;
+
+        reporter.finish();
+
+        Ok((cache_path, downloaded))
+    }
+
+    /// Download the package archive into a temp file within the cache, checking that every byte
+    /// the server said it would send was received.
+    async fn fetch(
+        &self,
+        url: &str,
+        cache_dir: &Path,
+        reporter: &dyn ProgressBarTrait,
+    ) -> Result<(TempFile<fs::File>, u64), Error> {
+        let download_result = CLIENT.get(url).send_traced().await?.error_for_status()?;
+        let download_size = download_result.content_length();
+
+        if let Some(download_size) = download_size {
+            reporter.set_length(download_size);
+        }
+
         let mut download_stream = download_result.bytes_stream();
 
-        let mut temp_file = cache::get_temp_zip_file(&self.identifier).await?;
+        let mut temp_file = cache::get_temp_zip_file(cache_dir, &self.identifier).await?;
         let zip_file = temp_file.file_mut();
+        let mut downloaded = 0;
 
         while let Some(chunk) = download_stream.next().await {
-            let chunk = chunk.unwrap();
+            // Retrying mid-stream would duplicate the bytes already written, so the download is
+            // reported as incomplete instead.
+            let chunk = chunk.map_err(|_| Error::TruncatedResponse("package"))?;
             zip_file.write_all(&chunk).await.unwrap();
 
+            downloaded += chunk.len() as u64;
             reporter.inc(chunk.len() as u64);
         }
 
-        reporter.set_message(format!("Unzipping {progress_message}..."));
-
-        let cache_path = add_to_cache(&self.identifier, temp_file.into_std().await.file())?;
-
-        reporter.finish();
-
-        Ok(cache_path)
+        match download_size {
+            Some(expected) if expected != downloaded => Err(Error::CorruptDownload {
+                package: self.identifier.to_string(),
+                expected,
+                received: downloaded,
+            }),
+            _ => Ok((temp_file, downloaded)),
+        }
     }
 }
 
-fn add_to_cache(package: &PackageReference, zipfile: impl Read + Seek) -> Result<PathBuf, Error> {
-    let output_path = cache::get_cache_location(package);
+fn add_to_cache(
+    cache_dir: &Path,
+    package: &PackageReference,
+    zipfile: impl Read + Seek,
+) -> Result<PathBuf, Error> {
+    let output_path = long_path(&cache::get_cache_location(cache_dir, package));
 
     match std::fs::remove_dir_all(&output_path) {
         Ok(_) => (),
@@ -204,7 +274,155 @@ fn add_to_cache(package: &PackageReference, zipfile: impl Read + Seek) -> Result
     };
 
     std::fs::create_dir_all(&output_path).map_fs_error(&output_path)?;
-    zip::read::ZipArchive::new(zipfile)?.extract(&output_path)?;
+    extract_archive(zipfile, &output_path)?;
 
     Ok(output_path)
 }
+
+/// Extract a package archive into the given directory, rejecting any entry whose path is
+/// absolute or would otherwise escape the directory.
+fn extract_archive(zipfile: impl Read + Seek, directory: &Path) -> Result<(), Error> {
+    let mut archive = zip::read::ZipArchive::new(zipfile)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+
+        let out_path = file
+            .enclosed_name()
+            .map(|x| directory.join(x))
+            .ok_or_else(|| Error::MaliciousArchive(file.name().to_string()))?;
+
+        if file.is_dir() {
+            std::fs::create_dir_all(&out_path).map_fs_error(&out_path)?;
+            continue;
+        }
+
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent).map_fs_error(parent)?;
+        }
+
+        let mut out_file = std::fs::File::create(&out_path).map_fs_error(&out_path)?;
+        std::io::copy(&mut file, &mut out_file).map_fs_error(&out_path)?;
+
+        // Keep executable bits for native mods, but never setuid, setgid or sticky bits.
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&out_path, std::fs::Permissions::from_mode(mode & 0o777))
+                .map_fs_error(&out_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use indicatif::ProgressBar;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+    use crate::util::test_server::{response, serve};
+
+    fn archive(entries: &[&str]) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for entry in entries {
+            zip.start_file(*entry, FileOptions::default()).unwrap();
+            zip.write_all(entry.as_bytes()).unwrap();
+        }
+
+        Cursor::new(zip.finish().unwrap().into_inner())
+    }
+
+    #[test]
+    fn extract_archive_extracts_nested_entries() {
+        let dir = tempfile::tempdir().unwrap();
+
+        extract_archive(archive(&["manifest.json", "plugins/mod.dll"]), dir.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("plugins/mod.dll")).unwrap(),
+            "plugins/mod.dll"
+        );
+        assert!(dir.path().join("manifest.json").is_file());
+    }
+
+    #[test]
+    fn extract_archive_rejects_parent_traversal() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("package");
+        std::fs::create_dir(&dir).unwrap();
+
+        let result = extract_archive(archive(&["plugins/../../escaped.txt"]), &dir);
+
+        assert!(
+            matches!(result, Err(Error::MaliciousArchive(x)) if x == "plugins/../../escaped.txt")
+        );
+        assert!(!root.path().join("escaped.txt").exists());
+    }
+
+    #[test]
+    fn extract_archive_rejects_absolute_paths() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = extract_archive(archive(&["/escaped.txt"]), dir.path());
+
+        assert!(matches!(result, Err(Error::MaliciousArchive(_))));
+    }
+
+    fn remote_package(address: String) -> Package {
+        Package {
+            identifier: "Author-Mod-1.0.0".parse().unwrap(),
+            source: PackageSource::Remote(format!("{address}/package.zip")),
+            dependencies: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn download_retries_truncated_response() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let body = archive(&["manifest.json", "plugins/mod.dll"]).into_inner();
+        let address = serve(vec![
+            response(&body, body.len() / 2),
+            response(&body, body.len()),
+        ])
+        .await;
+
+        let package = remote_package(address);
+        let (cache_path, downloaded) = package
+            .download(cache_dir.path(), &ProgressBar::hidden())
+            .await
+            .unwrap();
+
+        assert_eq!(downloaded, body.len() as u64);
+        assert!(cache_path.join("plugins/mod.dll").is_file());
+    }
+
+    #[tokio::test]
+    async fn download_fails_when_retry_is_truncated() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let body = archive(&["manifest.json"]).into_inner();
+        let address = serve(vec![
+            response(&body, body.len() / 2),
+            response(&body, body.len() / 2),
+        ])
+        .await;
+
+        let package = remote_package(address);
+        let result = package
+            .download(cache_dir.path(), &ProgressBar::hidden())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::TruncatedResponse(_) | Error::CorruptDownload { .. })
+        ));
+        // Neither the partial download nor a partial extraction is left within the cache.
+        assert_eq!(std::fs::read_dir(cache_dir.path()).unwrap().count(), 0);
+    }
+}
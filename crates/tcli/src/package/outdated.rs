@@ -0,0 +1,39 @@
+use futures_util::future::try_join_all;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::ts::experimental::package;
+use crate::ts::package_reference::PackageReference;
+use crate::ts::version::Version;
+
+/// The installed and latest available version of a package.
+#[derive(Serialize, Debug)]
+pub struct PackageVersions {
+    pub package: String,
+    pub current: Version,
+    pub latest: Version,
+}
+
+impl PackageVersions {
+    pub fn is_outdated(&self) -> bool {
+        self.latest > self.current
+    }
+}
+
+/// Look up the latest version of each package within the repository, sorted by package name.
+pub async fn latest_versions(packages: &[PackageReference]) -> Result<Vec<PackageVersions>, Error> {
+    let jobs = packages.iter().map(|x| async move {
+        let listing = package::get_metadata(&x.namespace, &x.name).await?;
+
+        Ok::<_, Error>(PackageVersions {
+            package: x.to_loose_ident_string(),
+            current: x.version,
+            latest: listing.latest.version,
+        })
+    });
+
+    let mut versions = try_join_all(jobs).await?;
+    versions.sort_by(|a, b| a.package.cmp(&b.package));
+
+    Ok(versions)
+}
@@ -0,0 +1,42 @@
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::Add;
+
+use serde::Serialize;
+
+/// Disk and network usage of installing one or more packages into a project.
+#[derive(Serialize, Debug, Default, Clone, Copy)]
+pub struct InstallReport {
+    pub packages: u64,
+    pub downloaded_bytes: u64,
+    pub installed_files: u64,
+    pub installed_bytes: u64,
+}
+
+impl Add for InstallReport {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        InstallReport {
+            packages: self.packages + rhs.packages,
+            downloaded_bytes: self.downloaded_bytes + rhs.downloaded_bytes,
+            installed_files: self.installed_files + rhs.installed_files,
+            installed_bytes: self.installed_bytes + rhs.installed_bytes,
+        }
+    }
+}
+
+impl Sum for InstallReport {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(InstallReport::default(), Add::add)
+    }
+}
+
+impl Display for InstallReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Packages installed: {}", self.packages)?;
+        writeln!(f, "Downloaded:         {} bytes", self.downloaded_bytes)?;
+        writeln!(f, "Files installed:    {}", self.installed_files)?;
+        write!(f, "Disk used:          {} bytes", self.installed_bytes)
+    }
+}
@@ -1,18 +1,130 @@
 use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
 
 use futures_util::future::try_join_all;
+use itertools::Itertools;
+use serde::Serialize;
 
-use super::Package;
+use super::report::InstallReport;
+use super::{cache, Package, PackageSource};
 use crate::error::Error;
 use crate::project::lock::LockFile;
 use crate::project::ProjectPath;
+use crate::ts::experimental::models::package::PackageMetadata;
+use crate::ts::experimental::package;
 use crate::ts::package_reference::PackageReference;
-use crate::ui::reporter::Reporter;
+use crate::ts::version::Version;
+use crate::ui::reporter::{Event, Reporter};
+
+/// A package pinned by the project which the resolver upgraded to satisfy another package.
+#[derive(Serialize, Debug)]
+pub struct Conflict {
+    pub package: String,
+    pub pinned: Version,
+    pub required: Version,
+    pub required_by: String,
+}
+
+/// Why a package was resolved, where it was resolved from, and which versions were considered.
+#[derive(Serialize, Debug)]
+pub struct Explanation {
+    pub package: String,
+    pub chosen: Version,
+    /// Where the chosen version was resolved from: `remote`, `cache` or `local`.
+    pub origin: &'static str,
+    /// Packages which depend on this one, or `manifest` when the project requested it directly.
+    pub required_by: Vec<String>,
+    /// Every version requested by the manifest or a dependent package.
+    pub requested: Vec<Version>,
+    /// Versions already present within the package cache.
+    pub cached: Vec<Version>,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let list = |versions: &[Version]| versions.iter().join(", ");
+
+        write!(
+            f,
+            "{}-{} from {}, required by {}; requested {}; cached {}",
+            self.package,
+            self.chosen,
+            self.origin,
+            self.required_by.join(", "),
+            list(&self.requested),
+            if self.cached.is_empty() {
+                "none".to_string()
+            } else {
+                list(&self.cached)
+            },
+        )
+    }
+}
+
+/// A package which is already installed at a different version than the one resolved.
+#[derive(Serialize, Debug)]
+pub struct Upgrade {
+    pub package: String,
+    pub from: Version,
+    pub to: Version,
+}
+
+/// The changes applying a resolution would make to the project, see [`PackageResolver::plan`].
+#[derive(Serialize, Debug)]
+pub struct InstallPlan {
+    pub to_install: Vec<String>,
+    pub to_upgrade: Vec<Upgrade>,
+    /// Packages which will no longer be tracked by the lockfile.
+    pub to_remove: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+    /// The size of the packages which need to be downloaded, if it could be determined.
+    pub total_download_bytes: Option<u64>,
+}
+
+impl Display for InstallPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} packages will be installed, {} will be upgraded and {} will be removed.",
+            self.to_install.len(),
+            self.to_upgrade.len(),
+            self.to_remove.len()
+        )?;
+
+        for package in &self.to_install {
+            write!(f, "\n+ {package}")?;
+        }
+        for upgrade in &self.to_upgrade {
+            write!(
+                f,
+                "\n^ {} {} -> {}",
+                upgrade.package, upgrade.from, upgrade.to
+            )?;
+        }
+        for package in &self.to_remove {
+            write!(f, "\n- {package}")?;
+        }
+        for conflict in &self.conflicts {
+            write!(
+                f,
+                "\n! {} is pinned to {} but {} requires {}",
+                conflict.package, conflict.pinned, conflict.required_by, conflict.required
+            )?;
+        }
+
+        match self.total_download_bytes {
+            Some(bytes) => write!(f, "\nDownload size: {bytes} bytes"),
+            None => write!(f, "\nDownload size: unknown"),
+        }
+    }
+}
 
 pub struct PackageResolver {
     pub packages_to_install: Vec<Package>,
-    lockfile: LockFile,
     project: ProjectPath,
+    cache_dir: PathBuf,
+    ignore_locked: bool,
 }
 
 impl PackageResolver {
@@ -23,9 +135,22 @@ impl PackageResolver {
     /// 1. Packages already installed into the project.
     /// 2. Dependencies specified within local packages within the cache.
     /// 3. Dependencies specified within the remote repository.
+    ///
+    /// Packages are cached within the default package cache in TCLI_HOME, see
+    /// [`PackageResolver::resolve_with_cache`] to use a different cache directory.
     pub async fn resolve_new(
         packages: Vec<PackageReference>,
         project: &ProjectPath,
+    ) -> Result<Self, Error> {
+        Self::resolve_with_cache(packages, project, cache::default_cache_dir()).await
+    }
+
+    /// Generate a deduplicated list of package dependencies, resolving and caching packages
+    /// within the given cache directory.
+    pub async fn resolve_with_cache(
+        packages: Vec<PackageReference>,
+        project: &ProjectPath,
+        cache_dir: &Path,
     ) -> Result<Self, Error> {
         let mut dep_map: HashMap<String, Package> = HashMap::new();
         let mut queue: VecDeque<PackageReference> = VecDeque::from(packages.clone());
@@ -34,7 +159,7 @@ impl PackageResolver {
         // Generate top-level package dependencies first. We then iterate down through the tree
         // until all have been resolved.
         while let Some(package_ident) = queue.pop_front() {
-            let package = Package::resolve_new(package_ident).await?;
+            let package = Package::resolve_new(package_ident, cache_dir).await?;
             let package_deps = package
                 .dependencies
                 .iter()
@@ -47,7 +172,7 @@ impl PackageResolver {
                 })
                 .map(|dep| async {
                     let loose_ident = dep.to_loose_ident_string();
-                    let dep_package = Package::resolve_new(dep.clone()).await?;
+                    let dep_package = Package::resolve_new(dep.clone(), cache_dir).await?;
 
                     Ok::<(String, Package), Error>((loose_ident, dep_package))
                 });
@@ -59,29 +184,302 @@ impl PackageResolver {
         }
 
         let packages_to_install = dep_map.into_values().collect::<Vec<_>>();
-        let lockfile = LockFile::open_or_new(&project.path().join("Thunderstore.lock"))?;
 
         Ok(PackageResolver {
             packages_to_install,
-            lockfile,
             project: project.clone(),
+            cache_dir: cache_dir.to_path_buf(),
+            ignore_locked: false,
         })
     }
 
-    /// Apply the newly resolved packages onto the previously specified project.
-    pub async fn apply(mut self, reporter: Box<dyn Reporter>) -> Result<(), Error> {
-        let multi = reporter.create_progress();
+    /// Discard the project's existing lockfile when applying, replacing it with one which only
+    /// contains the newly resolved packages. This recovers projects whose lockfile is corrupt.
+    pub fn ignore_locked(self, ignore_locked: bool) -> Self {
+        PackageResolver {
+            ignore_locked,
+            ..self
+        }
+    }
+
+    /// Find the packages pinned by the project whose version is overridden by a higher version
+    /// required by another resolved package.
+    pub fn conflicts(&self, pinned: &[PackageReference]) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        for package in &self.packages_to_install {
+            for dep in &package.dependencies {
+                let pin = pinned
+                    .iter()
+                    .find(|x| x.to_loose_ident_string() == dep.to_loose_ident_string());
+
+                match pin {
+                    Some(pin) if pin.version < dep.version => conflicts.push(Conflict {
+                        package: dep.to_loose_ident_string(),
+                        pinned: pin.version,
+                        required: dep.version,
+                        required_by: package.identifier.to_string(),
+                    }),
+                    _ => (),
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Describe how each resolved package was chosen, given the root packages the resolution
+    /// started from.
+    pub fn explain(&self, roots: &[PackageReference]) -> Vec<Explanation> {
+        self.packages_to_install
+            .iter()
+            .sorted_by(|a, b| a.identifier.cmp(&b.identifier))
+            .map(|package| {
+                let loose_ident = package.identifier.to_loose_ident_string();
+                let mut required_by = Vec::new();
+                let mut requested = Vec::new();
+
+                for root in roots {
+                    if root.to_loose_ident_string() == loose_ident {
+                        required_by.push("manifest".to_string());
+                        requested.push(root.version);
+                    }
+                }
+
+                for dependent in &self.packages_to_install {
+                    for dep in &dependent.dependencies {
+                        if dep.to_loose_ident_string() == loose_ident {
+                            required_by.push(dependent.identifier.to_string());
+                            requested.push(dep.version);
+                        }
+                    }
+                }
+
+                requested.sort();
+                requested.dedup();
+
+                Explanation {
+                    package: loose_ident,
+                    chosen: package.identifier.version,
+                    origin: match package.source {
+                        PackageSource::Remote(_) => "remote",
+                        PackageSource::Cache(_) => "cache",
+                        PackageSource::Local(_) => "local",
+                    },
+                    required_by,
+                    requested,
+                    cached: cache::cached_versions(&self.cache_dir, &package.identifier),
+                }
+            })
+            .collect()
+    }
 
-        let jobs = self
+    /// Fetch the repository listing of every resolved package, failing if any listing can't be
+    /// fetched, for example because the repository is unreachable.
+    pub async fn listings(&self) -> Result<Vec<(&Package, PackageMetadata)>, Error> {
+        let jobs = self.packages_to_install.iter().map(|x| async move {
+            let listing = package::get_metadata(&x.identifier.namespace, &x.identifier.name).await;
+            listing.map(|listing| (x, listing))
+        });
+
+        try_join_all(jobs).await
+    }
+
+    /// Compare the resolved packages against the project's lockfile, describing what applying
+    /// them would change without installing anything. Roots are the packages the resolution
+    /// started from, which are checked for conflicts.
+    pub async fn plan(&self, roots: &[PackageReference]) -> Result<InstallPlan, Error> {
+        let lockfile_path = self.project.path().join("Thunderstore.lock");
+        let locked = match LockFile::open_or_new(&lockfile_path) {
+            Ok(lockfile) => lockfile.packages,
+            // The lockfile is replaced when it's ignored, so a corrupt one doesn't matter.
+            Err(_) if self.ignore_locked => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut to_install = Vec::new();
+        let mut to_upgrade = Vec::new();
+
+        for package in &self.packages_to_install {
+            let loose_ident = package.identifier.to_loose_ident_string();
+
+            match locked.get(&loose_ident).map(|x| x.identifier.version) {
+                None => to_install.push(package.identifier.to_string()),
+                Some(from) if from != package.identifier.version => to_upgrade.push(Upgrade {
+                    package: loose_ident,
+                    from,
+                    to: package.identifier.version,
+                }),
+                Some(_) => (),
+            }
+        }
+
+        let mut to_remove = match self.ignore_locked {
+            true => locked
+                .iter()
+                .filter(|(loose_ident, _)| {
+                    !self
+                        .packages_to_install
+                        .iter()
+                        .any(|x| x.identifier.to_loose_ident_string() == **loose_ident)
+                })
+                .map(|(_, package)| package.identifier.to_string())
+                .collect(),
+            false => vec![],
+        };
+
+        let downloads = self
             .packages_to_install
             .iter()
-            .map(|package| package.add(&self.project, multi.add_bar()));
+            .filter_map(|x| match &x.source {
+                PackageSource::Remote(url) => Some(package::get_file_size(url)),
+                _ => None,
+            });
+        let total_download_bytes = try_join_all(downloads)
+            .await
+            .ok()
+            .and_then(|sizes| sizes.into_iter().sum());
+
+        to_install.sort();
+        to_upgrade.sort_by(|a, b| a.package.cmp(&b.package));
+        to_remove.sort();
+
+        Ok(InstallPlan {
+            to_install,
+            to_upgrade,
+            to_remove,
+            conflicts: self.conflicts(roots),
+            total_download_bytes,
+        })
+    }
+
+    /// Apply the newly resolved packages onto the previously specified project, returning the
+    /// combined disk and network usage of the installation.
+    pub async fn apply(self, reporter: &dyn Reporter) -> Result<InstallReport, Error> {
+        let lockfile_path = self.project.path().join("Thunderstore.lock");
+        let mut lockfile = if self.ignore_locked {
+            LockFile::new(&lockfile_path)
+        } else {
+            LockFile::open_or_new(&lockfile_path)?
+        };
+
+        let multi = reporter.create_progress();
+
+        let jobs = self.packages_to_install.iter().map(|package| async {
+            let report = package
+                .add(&self.project, &self.cache_dir, multi.add_bar())
+                .await?;
+
+            reporter.event(&Event::PackageInstalled {
+                package: package.identifier.to_string(),
+                downloaded_bytes: report.downloaded_bytes,
+                installed_files: report.installed_files,
+                installed_bytes: report.installed_bytes,
+            });
+
+            Ok::<_, Error>(report)
+        });
+
+        let report = try_join_all(jobs).await?.into_iter().sum();
+
+        lockfile.merge(&self.packages_to_install);
+        lockfile.commit()?;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(reference: &str, dependencies: &[&str]) -> Package {
+        Package {
+            identifier: reference.parse().unwrap(),
+            source: PackageSource::Local(PathBuf::from(format!("{reference}.zip"))),
+            dependencies: dependencies.iter().map(|x| x.parse().unwrap()).collect(),
+        }
+    }
+
+    fn resolver(dir: &Path, locked: &[Package], resolved: Vec<Package>) -> PackageResolver {
+        std::fs::write(dir.join("Thunderstore.toml"), "").unwrap();
+
+        let mut lockfile = LockFile::new(&dir.join("Thunderstore.lock"));
+        lockfile.merge(locked);
+        lockfile.commit().unwrap();
+
+        PackageResolver {
+            packages_to_install: resolved,
+            project: ProjectPath::new(dir).unwrap(),
+            cache_dir: dir.join("cache"),
+            ignore_locked: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn plan_describes_installs_and_upgrades() {
+        let dir = tempfile::tempdir().unwrap();
+        let resolver = resolver(
+            dir.path(),
+            &[package("A-One-1.0.0", &[]), package("B-Two-1.0.0", &[])],
+            vec![
+                package("A-One-1.0.0", &[]),
+                package("B-Two-2.0.0", &[]),
+                package("C-Three-1.0.0", &[]),
+            ],
+        );
+
+        let plan = resolver.plan(&[]).await.unwrap();
+
+        assert_eq!(plan.to_install, ["C-Three-1.0.0"]);
+        assert_eq!(plan.to_upgrade.len(), 1);
+        assert_eq!(plan.to_upgrade[0].package, "B-Two");
+        assert_eq!(plan.to_upgrade[0].from, Version::new(1, 0, 0));
+        assert_eq!(plan.to_upgrade[0].to, Version::new(2, 0, 0));
+        assert!(plan.to_remove.is_empty());
+        assert_eq!(plan.total_download_bytes, Some(0));
+    }
+
+    #[tokio::test]
+    async fn plan_removes_unresolved_packages_when_ignoring_lockfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let resolver = resolver(
+            dir.path(),
+            &[package("A-One-1.0.0", &[]), package("B-Two-1.0.0", &[])],
+            vec![package("A-One-1.0.0", &[])],
+        );
+
+        let plan = resolver.plan(&[]).await.unwrap();
+        assert!(plan.to_remove.is_empty());
+
+        let plan = resolver.ignore_locked(true).plan(&[]).await.unwrap();
+        assert!(plan.to_install.is_empty());
+        assert!(plan.to_upgrade.is_empty());
+        assert_eq!(plan.to_remove, ["B-Two-1.0.0"]);
+    }
 
-        try_join_all(jobs).await?;
+    #[tokio::test]
+    async fn plan_reports_overridden_pins() {
+        let dir = tempfile::tempdir().unwrap();
+        let resolver = resolver(
+            dir.path(),
+            &[],
+            vec![
+                package("A-One-1.0.0", &["B-Two-2.0.0"]),
+                package("B-Two-2.0.0", &[]),
+            ],
+        );
 
-        self.lockfile.merge(&self.packages_to_install);
-        self.lockfile.commit()?;
+        let roots = [
+            "A-One-1.0.0".parse().unwrap(),
+            "B-Two-1.0.0".parse().unwrap(),
+        ];
+        let plan = resolver.plan(&roots).await.unwrap();
 
-        Ok(())
+        assert_eq!(plan.to_install, ["A-One-1.0.0", "B-Two-2.0.0"]);
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].package, "B-Two");
+        assert_eq!(plan.conflicts[0].pinned, Version::new(1, 0, 0));
+        assert_eq!(plan.conflicts[0].required_by, "A-One-1.0.0");
     }
 }
@@ -0,0 +1,99 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::error::{Error, IoResultToTcli};
+use crate::util::file;
+use crate::TCLI_HOME;
+
+static KNOWN_PROJECTS: Lazy<PathBuf> = Lazy::new(|| TCLI_HOME.join("projects.json"));
+
+/// A project directory which tcli has operated on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KnownProject {
+    pub path: PathBuf,
+    /// Unix timestamp of the last time tcli operated on the project.
+    #[serde(default)]
+    pub last_accessed: Option<i64>,
+}
+
+impl KnownProject {
+    /// Whether the project still exists where it was recorded.
+    pub fn exists(&self) -> bool {
+        self.path.join("Thunderstore.toml").is_file()
+    }
+
+    pub fn last_accessed(&self) -> Option<OffsetDateTime> {
+        self.last_accessed
+            .and_then(|x| OffsetDateTime::from_unix_timestamp(x).ok())
+    }
+}
+
+/// Read every project directory which tcli has operated on. Projects may have been moved or
+/// deleted since they were recorded.
+pub fn read() -> Result<Vec<KnownProject>, Error> {
+    match fs::read_to_string(KNOWN_PROJECTS.as_path()) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(Error::FileIoError(KNOWN_PROJECTS.to_path_buf(), e)),
+    }
+}
+
+/// Record a project directory within the known projects, updating when it was last accessed.
+pub fn record(project_dir: &Path) -> Result<(), Error> {
+    let path = project_dir.canonicalize().map_fs_error(project_dir)?;
+    let last_accessed = Some(OffsetDateTime::now_utc().unix_timestamp());
+    let mut projects = read()?;
+
+    match projects.iter_mut().find(|x| x.path == path) {
+        Some(project) => project.last_accessed = last_accessed,
+        None => projects.push(KnownProject {
+            path,
+            last_accessed,
+        }),
+    }
+
+    write(&projects)
+}
+
+/// Forget the given project directory, returning whether it was known.
+pub fn forget(project_dir: &Path) -> Result<bool, Error> {
+    // The directory may no longer exist, in which case it's compared as given.
+    let path = project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf());
+    let mut projects = read()?;
+    let count = projects.len();
+
+    projects.retain(|x| x.path != path);
+
+    if projects.len() == count {
+        return Ok(false);
+    }
+
+    write(&projects)?;
+    Ok(true)
+}
+
+/// Forget every project which no longer exists, returning their directories.
+pub fn forget_stale() -> Result<Vec<PathBuf>, Error> {
+    let (projects, stale): (Vec<_>, Vec<_>) = read()?.into_iter().partition(|x| x.exists());
+
+    if !stale.is_empty() {
+        write(&projects)?;
+    }
+
+    Ok(stale.into_iter().map(|x| x.path).collect())
+}
+
+fn write(projects: &[KnownProject]) -> Result<(), Error> {
+    fs::create_dir_all(TCLI_HOME.as_path()).map_fs_error(TCLI_HOME.as_path())?;
+    file::write_atomic(
+        KNOWN_PROJECTS.as_path(),
+        serde_json::to_string_pretty(projects)?,
+    )
+}
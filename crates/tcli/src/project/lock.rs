@@ -1,11 +1,11 @@
 use std::collections::HashMap;
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::package::Package;
+use crate::util::file;
 use crate::Error;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -24,18 +24,24 @@ impl LockFile {
     pub fn open_or_new(path: &Path) -> Result<Self, Error> {
         if path.exists() {
             let contents = fs::read_to_string(path)?;
-            let lockfile = serde_json::from_str(&contents).unwrap();
+            let lockfile = serde_json::from_str(&contents)
+                .map_err(|e| Error::InvalidLockFile(path.to_path_buf(), e))?;
 
             Ok(LockFile {
                 path: path.to_path_buf(),
                 ..lockfile
             })
         } else {
-            Ok(LockFile {
-                version: 1,
-                path: path.to_path_buf(),
-                packages: HashMap::new(),
-            })
+            Ok(LockFile::new(path))
+        }
+    }
+
+    /// Creates a new, empty lockfile instance, ignoring any lockfile already on disk.
+    pub fn new(path: &Path) -> Self {
+        LockFile {
+            version: 1,
+            path: path.to_path_buf(),
+            packages: HashMap::new(),
         }
     }
 
@@ -49,17 +55,15 @@ impl LockFile {
         self.packages.extend(new_packages);
     }
 
-    /// Writes the lockfile to disk.
-    pub fn commit(self) -> Result<(), Error> {
-        let mut lockfile = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .open(&self.path)?;
+    /// Removes every package from the lockfile.
+    pub fn clear(&mut self) {
+        self.packages.clear();
+    }
 
+    /// Writes the lockfile to disk, replacing the previous lockfile atomically.
+    pub fn commit(self) -> Result<(), Error> {
         let new_contents = serde_json::to_string_pretty(&self).unwrap();
-        lockfile.write_all(new_contents.as_bytes())?;
-
-        Ok(())
+        file::write_atomic(&self.path, new_contents)
     }
 }
 
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
 use crate::project::overrides::ProjectOverrides;
+use crate::project::ProjectKind;
 use crate::ts::package_reference::{self, PackageReference};
 use crate::ts::version::Version;
 
@@ -59,7 +60,34 @@ impl ProjectManifest {
         Ok(manifest)
     }
 
+    /// Dev projects describe a package to build and publish, and are the only kind with a
+    /// package table. Everything else is a profile.
+    pub fn kind(&self) -> ProjectKind {
+        match self.package {
+            Some(_) => ProjectKind::Dev,
+            None => ProjectKind::Profile,
+        }
+    }
+
+    /// Fail with [`Error::WrongProjectKind`] if this isn't the expected kind of project.
+    pub fn ensure_kind(&self, expected: ProjectKind) -> Result<(), Error> {
+        match self.kind() {
+            found if found == expected => Ok(()),
+            found => Err(Error::WrongProjectKind { expected, found }),
+        }
+    }
+
     pub fn apply_overrides(&mut self, overrides: ProjectOverrides) -> Result<(), Error> {
+        // Only packages have package and build tables to override.
+        if overrides.namespace.is_some()
+            || overrides.name.is_some()
+            || overrides.version.is_some()
+            || overrides.output_dir.is_some()
+            || overrides.reproducible.is_some()
+        {
+            self.ensure_kind(ProjectKind::Dev)?;
+        }
+
         if overrides.namespace.is_some() || overrides.name.is_some() || overrides.version.is_some()
         {
             let package = self
@@ -82,6 +110,12 @@ impl ProjectManifest {
                 .ok_or(Error::MissingTable("build"))?
                 .outdir = output_dir;
         }
+        if let Some(reproducible) = overrides.reproducible {
+            self.build
+                .as_mut()
+                .ok_or(Error::MissingTable("build"))?
+                .reproducible = reproducible;
+        }
         if let Some(repository) = overrides.repository {
             self.config.repository = Some(repository);
         }
@@ -141,6 +175,12 @@ pub struct BuildData {
     pub outdir: PathBuf,
     #[serde(default)]
     pub copy: Vec<CopyPath>,
+    #[serde(default)]
+    pub reproducible: bool,
+    /// Glob patterns of paths to leave out of the package, matched against the path relative to
+    /// each copy source. Matching directories are skipped along with everything within them.
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 impl Default for BuildData {
@@ -150,6 +190,8 @@ impl Default for BuildData {
             readme: "./README.md".into(),
             outdir: "./build".into(),
             copy: vec![CopyPath::default()],
+            reproducible: false,
+            exclude: vec![],
         }
     }
 }
@@ -195,6 +237,31 @@ pub struct DependencyData {
     pub dev_dependencies: Vec<PackageReference>,
 }
 
+impl DependencyData {
+    /// Union the dependencies of another project into this one. When both projects depend on
+    /// the same package the highest version wins, matching the resolver.
+    pub fn merge(&mut self, other: &DependencyData) {
+        merge_references(&mut self.dependencies, &other.dependencies);
+        merge_references(&mut self.dev_dependencies, &other.dev_dependencies);
+    }
+}
+
+fn merge_references(target: &mut Vec<PackageReference>, other: &[PackageReference]) {
+    for reference in other {
+        let existing = target
+            .iter_mut()
+            .find(|x| x.to_loose_ident_string() == reference.to_loose_ident_string());
+
+        match existing {
+            Some(existing) if existing.version < reference.version => {
+                existing.version = reference.version
+            }
+            Some(_) => (),
+            None => target.push(reference.clone()),
+        }
+    }
+}
+
 impl Default for DependencyData {
     fn default() -> Self {
         DependencyData {
@@ -208,3 +275,70 @@ impl Default for DependencyData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(references: &[&str]) -> DependencyData {
+        DependencyData {
+            dependencies: references.iter().map(|x| x.parse().unwrap()).collect(),
+            dev_dependencies: vec![],
+        }
+    }
+
+    #[test]
+    fn package_overrides_require_dev_project() {
+        let mut manifest = ProjectManifest::default_profile_project();
+        let overrides = ProjectOverrides::new().reproducible_override(Some(true));
+
+        assert!(matches!(
+            manifest.apply_overrides(overrides),
+            Err(Error::WrongProjectKind {
+                expected: ProjectKind::Dev,
+                found: ProjectKind::Profile,
+            })
+        ));
+        assert!(manifest
+            .apply_overrides(ProjectOverrides::new().repository_override(Some("x".into())))
+            .is_ok());
+    }
+
+    #[test]
+    fn merge_disjoint_dependencies_unions() {
+        let mut data = dependencies(&["A-One-1.0.0"]);
+        data.merge(&dependencies(&["B-Two-2.0.0"]));
+
+        assert_eq!(
+            data.dependencies,
+            dependencies(&["A-One-1.0.0", "B-Two-2.0.0"]).dependencies
+        );
+    }
+
+    #[test]
+    fn merge_overlapping_dependencies_keeps_highest_version() {
+        let mut data = dependencies(&["A-One-1.0.0", "B-Two-2.1.0"]);
+        data.merge(&dependencies(&["A-One-1.2.0", "B-Two-2.0.0"]));
+
+        assert_eq!(
+            data.dependencies,
+            dependencies(&["A-One-1.2.0", "B-Two-2.1.0"]).dependencies
+        );
+    }
+
+    #[test]
+    fn merge_keeps_dev_dependencies_separate() {
+        let mut data = dependencies(&["A-One-1.0.0"]);
+        let other = DependencyData {
+            dependencies: vec![],
+            dev_dependencies: vec!["A-One-2.0.0".parse().unwrap()],
+        };
+        data.merge(&other);
+
+        assert_eq!(
+            data.dependencies,
+            dependencies(&["A-One-1.0.0"]).dependencies
+        );
+        assert_eq!(data.dev_dependencies, other.dev_dependencies);
+    }
+}
@@ -1,52 +1,125 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub use publish::publish;
+use wildmatch::WildMatch;
 use zip::write::FileOptions;
 
+use crate::config::Vars;
 use crate::error::{Error, IoResultToTcli};
+use crate::project::lock::LockFile;
 use crate::project::manifest::ProjectManifest;
 use crate::project::overrides::ProjectOverrides;
+use crate::project::writer::{DirPackageWriter, PackageWriter, ZipPackageWriter};
 use crate::ts::package_manifest::PackageManifestV1;
 
+pub mod known;
 pub mod manifest;
 pub mod overrides;
 pub mod lock;
 mod publish;
+mod writer;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProjectKind {
-    Dev(ProjectOverrides),
+    /// A project which is built into a package and published.
+    Dev,
+    /// A project which mods are installed into to be played.
     Profile,
 }
 
+impl Display for ProjectKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ProjectKind::Dev => "package",
+            ProjectKind::Profile => "profile",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// The project path used when none is given, the manifest within the current directory.
+pub const DEFAULT_MANIFEST: &str = "./Thunderstore.toml";
+
 #[derive(Clone)]
 pub struct ProjectPath(PathBuf);
 
 impl ProjectPath {
+    /// Open the project at the given directory, or the directory containing the given project
+    /// file, creating its `.tcli` directory if it doesn't exist yet. When the path is
+    /// [`DEFAULT_MANIFEST`] the project is discovered from the current directory and its parents,
+    /// so that commands work from anywhere within a project.
     pub fn new(path: &Path) -> Result<ProjectPath, Error> {
-        let path = path.to_path_buf();
+        let cwd = Path::new(".").canonicalize().map_fs_error(".")?;
+        let project = Self::locate(path, &cwd)?;
 
-        if !path.exists() {
-            return Err(Error::NoProjectFile(path));
+        // Tolerates the directory already existing, as another tcli process may create it first.
+        if project.path().join("Thunderstore.toml").is_file() {
+            fs::create_dir_all(project.tcli_dir()).map_fs_error(project.tcli_dir())?;
         }
 
-        let root_dir = if path.is_file() {
-            path.parent().unwrap().to_path_buf()
-        } else {
-            path
-        };
+        project.validate()?;
+
+        // Failing to record a known project shouldn't prevent working on it.
+        let _ = known::record(project.path());
 
-        if !root_dir.join("Thunderstore.toml").is_file() {
-            return Err(Error::NoProjectFile(root_dir));
+        Ok(project)
+    }
+
+    /// Resolve the project a command was given, discovering it from `cwd` when the path is
+    /// [`DEFAULT_MANIFEST`].
+    fn locate(path: &Path, cwd: &Path) -> Result<ProjectPath, Error> {
+        match path == Path::new(DEFAULT_MANIFEST) {
+            true => Self::discover(cwd),
+            false if path.exists() => Ok(Self::open_unchecked(path)),
+            false => Err(Error::NoProjectFile(path.to_path_buf())),
         }
+    }
 
-        if !root_dir.join(".tcli/").is_dir() {
-            fs::create_dir(root_dir.join(".tcli/"))?;
+    /// Find the nearest directory containing a project manifest, starting at the given directory
+    /// and walking up through its parents.
+    pub fn discover(start: &Path) -> Result<ProjectPath, Error> {
+        start
+            .ancestors()
+            .find(|x| x.join("Thunderstore.toml").is_file())
+            .map(|x| ProjectPath(x.to_path_buf()))
+            .ok_or_else(|| Error::NoProjectFile(start.to_path_buf()))
+    }
+
+    /// Resolve the project directory of the given path without checking that a project exists
+    /// there, for when only the project's paths are needed.
+    pub fn open_unchecked(path: &Path) -> ProjectPath {
+        // A bare file name has an empty parent, which is the current directory.
+        let root_dir = match path.is_file() {
+            true => path
+                .parent()
+                .filter(|x| !x.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+                .to_path_buf(),
+            false => path.to_path_buf(),
+        };
+
+        ProjectPath(root_dir)
+    }
+
+    /// Check that the directory contains a project, meaning it has both a manifest and a `.tcli`
+    /// directory.
+    pub fn validate(&self) -> Result<(), Error> {
+        if !self.path().join("Thunderstore.toml").is_file() || !self.tcli_dir().is_dir() {
+            return Err(Error::NoProjectFile(self.path().to_path_buf()));
         }
 
-        Ok(ProjectPath(root_dir))
+        Ok(())
+    }
+
+    fn tcli_dir(&self) -> PathBuf {
+        self.path().join(".tcli")
     }
 
     pub fn path(&self) -> &Path {
@@ -58,6 +131,7 @@ pub fn create_new(
     project_path: &Path,
     overwrite: bool,
     project_kind: ProjectKind,
+    overrides: ProjectOverrides,
 ) -> Result<(), Error> {
     let project_dir = project_path.parent().unwrap_or("./".as_ref());
 
@@ -65,23 +139,18 @@ pub fn create_new(
         return Err(Error::ProjectDirIsFile(project_dir.into()));
     }
 
-    if !project_dir.is_dir() {
-        fs::create_dir(project_dir).map_fs_error(project_dir)?;
-    }
+    fs::create_dir_all(project_dir).map_fs_error(project_dir)?;
 
-    let manifest = match project_kind {
-        ProjectKind::Dev(overrides) => {
-            let mut manifest = ProjectManifest::default_dev_project();
-            manifest.apply_overrides(overrides)?;
-            manifest
-        }
+    let mut manifest = match project_kind {
+        ProjectKind::Dev => ProjectManifest::default_dev_project(),
         ProjectKind::Profile => ProjectManifest::default_profile_project(),
     };
+    manifest.apply_overrides(overrides)?;
 
     let mut options = File::options();
     options.write(true);
     if overwrite {
-        options.create(true);
+        options.create(true).truncate(true);
     } else {
         options.create_new(true);
     }
@@ -102,6 +171,8 @@ pub fn create_new(
     )
     .unwrap();
 
+    let _ = known::record(project_dir);
+
     if manifest.package.is_none() {
         return Ok(());
     }
@@ -142,12 +213,102 @@ pub fn create_new(
     Ok(())
 }
 
-pub fn build(manifest: &ProjectManifest) -> Result<PathBuf, Error> {
+/// Remove every installed package from the project, leaving the manifest and imported games
+/// untouched. Returns the number of packages which were uninstalled.
+pub fn uninstall_all(project: &ProjectPath) -> Result<usize, Error> {
+    let mut lockfile = LockFile::open_or_new(&project.path().join("Thunderstore.lock"))?;
+    let count = lockfile.packages.len();
+
+    let project_state = project.path().join("project_state");
+    if project_state.is_dir() {
+        fs::remove_dir_all(&project_state).map_fs_error(&project_state)?;
+    }
+
+    lockfile.clear();
+    lockfile.commit()?;
+
+    Ok(count)
+}
+
+/// Remove every directory within the project's installed packages which doesn't belong to a
+/// package in the lockfile, left behind by interrupted or failed installs. Returns the removed
+/// paths, or only lists them without removing anything when `dry_run` is set.
+pub fn clean(project: &ProjectPath, dry_run: bool) -> Result<Vec<PathBuf>, Error> {
+    let lockfile = LockFile::open_or_new(&project.path().join("Thunderstore.lock"))?;
+    let tracked = lockfile
+        .packages
+        .values()
+        .map(|x| x.identifier.to_string())
+        .collect::<HashSet<_>>();
+
+    let project_state = project.path().join("project_state");
+    let entries = match fs::read_dir(&project_state) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::FileIoError(project_state, e)),
+    };
+
+    let mut untracked = Vec::new();
+    for entry in entries {
+        let entry = entry.map_fs_error(&project_state)?;
+        if !tracked.contains(&*entry.file_name().to_string_lossy()) {
+            untracked.push(entry.path());
+        }
+    }
+    untracked.sort();
+
+    if dry_run {
+        return Ok(untracked);
+    }
+
+    for path in &untracked {
+        match path.is_dir() {
+            true => fs::remove_dir_all(path).map_fs_error(path)?,
+            false => fs::remove_file(path).map_fs_error(path)?,
+        }
+    }
+
+    Ok(untracked)
+}
+
+/// The forms a package can be emitted in by [`build`].
+#[derive(Debug, Clone, Copy, Default)]
+pub enum BuildEmit {
+    /// A zip archive, ready to be published.
+    #[default]
+    Zip,
+    /// A loose directory containing exactly what would be placed in the archive.
+    Dir,
+    /// Both a zip archive and a loose directory.
+    Both,
+}
+
+/// The paths written by [`build`], depending on the requested [`BuildEmit`].
+#[derive(Debug, Default)]
+pub struct BuildOutput {
+    pub archive: Option<PathBuf>,
+    pub directory: Option<PathBuf>,
+}
+
+/// Run [`build`] on the blocking thread pool, so that writing a large package doesn't stall the
+/// async runtime.
+pub async fn build_blocking(
+    manifest: Arc<ProjectManifest>,
+    emit: BuildEmit,
+) -> Result<BuildOutput, Error> {
+    tokio::task::spawn_blocking(move || build(&manifest, emit))
+        .await
+        .expect("The build task panicked")
+}
+
+pub fn build(manifest: &ProjectManifest, emit: BuildEmit) -> Result<BuildOutput, Error> {
     let project_dir = manifest
         .project_dir
         .as_deref()
         .expect("Project should be loaded from a file to build");
 
+    manifest.ensure_kind(ProjectKind::Dev)?;
+
     let package = manifest
         .package
         .as_ref()
@@ -157,86 +318,571 @@ pub fn build(manifest: &ProjectManifest) -> Result<PathBuf, Error> {
         .as_ref()
         .ok_or(Error::MissingTable("build"))?;
 
-    let output_dir = project_dir.join(&build.outdir);
+    // Validate the readme up front so a broken package is never written to disk.
+    let readme_path = project_dir.join(&build.readme);
+    if !readme_path.is_file() {
+        return Err(Error::FileNotFound(readme_path));
+    }
 
-    let output_path = output_dir.join(format!(
-        "{}-{}-{}.zip",
-        package.namespace, package.name, package.version
-    ));
+    if is_blank(&readme_path)? {
+        return Err(Error::EmptyReadme(readme_path));
+    }
 
-    match fs::create_dir_all(output_path.parent().unwrap()) {
+    let output_dir = project_dir.join(&build.outdir);
+    let output_name = format!("{}-{}-{}", package.namespace, package.name, package.version);
+
+    match fs::create_dir_all(&output_dir) {
         Ok(_) => Ok(()),
         Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
-        Err(e) => Err(Error::FileIoError(output_path.clone(), e)),
+        Err(e) => Err(Error::FileIoError(output_dir.clone(), e)),
     }?;
 
-    let mut zip = zip::ZipWriter::new(
-        File::options()
-            .create(true)
-            .write(true)
-            .open(&output_path)
-            .map_fs_error(&output_path)?,
-    );
+    let options = if build.reproducible {
+        FileOptions::default().last_modified_time(reproducible_timestamp()?)
+    } else {
+        FileOptions::default()
+    };
+
+    let mut output = BuildOutput::default();
+    let mut writers: Vec<Box<dyn PackageWriter>> = Vec::new();
+
+    if matches!(emit, BuildEmit::Zip | BuildEmit::Both) {
+        let archive_path = output_dir.join(format!("{output_name}.zip"));
+        writers.push(Box::new(ZipPackageWriter::create(&archive_path, options)?));
+        output.archive = Some(archive_path);
+    }
+
+    if matches!(emit, BuildEmit::Dir | BuildEmit::Both) {
+        let directory_path = output_dir.join(&output_name);
+        writers.push(Box::new(DirPackageWriter::create(&directory_path)?));
+        output.directory = Some(directory_path);
+    }
+
+    let exclude = build
+        .exclude
+        .iter()
+        .map(|x| WildMatch::new(x))
+        .collect::<Vec<_>>();
 
+    let mut entries = Vec::new();
     for copy in &build.copy {
         let source_path = project_dir.join(&copy.source);
+        let inner_path = |file: &walkdir::DirEntry| {
+            file.path()
+                .strip_prefix(&source_path)
+                .expect("Path was made by walking source, but was not rooted in source?")
+                .to_path_buf()
+        };
 
         // first elem is always the root, even when the path given is to a file
-        for file in walkdir::WalkDir::new(&source_path).follow_links(true) {
+        let walker = walkdir::WalkDir::new(&source_path)
+            .follow_links(true)
+            .into_iter()
+            .filter_entry(|file| {
+                // Patterns always use forward slashes, regardless of the platform.
+                let path = inner_path(file).to_string_lossy().replace('\\', "/");
+                file.depth() == 0 || !exclude.iter().any(|x| x.matches(&path))
+            });
+
+        for file in walker {
             let file = file?;
+            let archive_path = copy.target.join(inner_path(&file));
 
-            let inner_path = file
-                .path()
-                .strip_prefix(&source_path)
-                .expect("Path was made by walking source, but was not rooted in source?");
+            // Copying a directory to the root of the package would otherwise add a "/" entry.
+            if !archive_path.as_os_str().is_empty() {
+                entries.push((archive_path, file));
+            }
+        }
+    }
+
+    // WalkDir yields entries in filesystem order, which differs between machines.
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let package_manifest = PackageManifestV1::from_manifest(
+        package.clone(),
+        manifest.dependencies.dependencies.clone(),
+    );
+    let icon_path = project_dir.join(&build.icon);
 
+    for writer in writers.iter_mut() {
+        for (archive_path, file) in &entries {
             if file.file_type().is_dir() {
-                zip.add_directory(
-                    copy.target.join(inner_path).to_string_lossy(),
-                    FileOptions::default(),
-                )?;
+                writer.add_directory(archive_path)?;
             } else if file.file_type().is_file() {
-                zip.start_file(
-                    copy.target.join(inner_path).to_string_lossy(),
-                    FileOptions::default(),
-                )?;
-                std::io::copy(
+                writer.add_file(
+                    archive_path,
                     &mut File::open(file.path()).map_fs_error(file.path())?,
-                    &mut zip,
                 )?;
             } else {
                 unreachable!("paths should always be either a file or a dir")
             }
         }
+
+        writer.write_file("manifest.json".as_ref(), &mut |file| {
+            Ok(serde_json::to_writer_pretty(file, &package_manifest)?)
+        })?;
+        writer.add_file(
+            "icon.png".as_ref(),
+            &mut File::open(&icon_path).map_fs_error(&icon_path)?,
+        )?;
+        writer.add_file(
+            "README.md".as_ref(),
+            &mut File::open(&readme_path).map_fs_error(&readme_path)?,
+        )?;
     }
 
-    zip.start_file("manifest.json", FileOptions::default())?;
-    write!(
-        zip,
-        "{}",
-        serde_json::to_string_pretty(&PackageManifestV1::from_manifest(
-            package.clone(),
-            manifest.dependencies.dependencies.clone()
-        ))
-        .unwrap()
-    )?;
+    for writer in writers {
+        writer.finish()?;
+    }
 
-    let icon_path = project_dir.join(&build.icon);
-    zip.start_file("icon.png", FileOptions::default())?;
-    std::io::copy(
-        &mut File::open(&icon_path).map_fs_error(icon_path)?,
-        &mut zip,
-    )?;
+    Ok(output)
+}
 
-    let readme_path = project_dir.join(&build.readme);
-    zip.start_file("README.md", FileOptions::default())?;
-    write!(
-        zip,
-        "{}",
-        fs::read_to_string(&readme_path).map_fs_error(readme_path)?
-    )?;
+/// Whether the file contains nothing but whitespace, read a line at a time so that a large file
+/// is never held in memory.
+fn is_blank(path: &Path) -> Result<bool, Error> {
+    let file = File::open(path).map_fs_error(path)?;
+
+    for line in BufReader::new(file).lines() {
+        if !line.map_fs_error(path)?.trim().is_empty() {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// The modification time given to every archive entry in a reproducible build. Honours
+/// SOURCE_DATE_EPOCH, falling back to the earliest time representable in a zip archive.
+fn reproducible_timestamp() -> Result<zip::DateTime, Error> {
+    let Ok(epoch) = Vars::SourceDateEpoch.into_var() else {
+        return Ok(zip::DateTime::default());
+    };
+
+    epoch
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .and_then(|x| time::OffsetDateTime::from_unix_timestamp(x).ok())
+        .and_then(|x| zip::DateTime::try_from(x).ok())
+        .ok_or(Error::InvalidSourceDateEpoch(epoch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{Package, PackageSource};
+
+    fn write_project(dir: &Path) {
+        fs::write(dir.join("Thunderstore.toml"), "").unwrap();
+        fs::create_dir_all(dir.join(".tcli")).unwrap();
+    }
+
+    #[test]
+    fn discover_finds_project_from_nested_subdirectory() {
+        let root = tempfile::tempdir().unwrap();
+        write_project(root.path());
+
+        let nested = root.path().join("src").join("plugins");
+        fs::create_dir_all(&nested).unwrap();
+
+        let project = ProjectPath::discover(&nested).unwrap();
+        assert_eq!(project.path(), root.path());
+    }
+
+    #[test]
+    fn discover_prefers_nearest_project() {
+        let root = tempfile::tempdir().unwrap();
+        write_project(root.path());
+
+        let inner = root.path().join("inner");
+        fs::create_dir_all(inner.join("nested")).unwrap();
+        write_project(&inner);
+
+        let project = ProjectPath::discover(&inner.join("nested")).unwrap();
+        assert_eq!(project.path(), inner);
+    }
+
+    #[test]
+    fn discover_without_project_fails() {
+        let root = tempfile::tempdir().unwrap();
+
+        // The temp directory may itself be nested within a project, which is then found instead.
+        match ProjectPath::discover(root.path()) {
+            Ok(project) => assert!(!project.path().starts_with(root.path())),
+            Err(e) => assert!(matches!(e, Error::NoProjectFile(_))),
+        }
+    }
+
+    #[test]
+    fn new_opens_project_and_creates_tcli_dir() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("Thunderstore.toml"), "").unwrap();
+
+        let project = ProjectPath::new(root.path()).unwrap();
+
+        assert_eq!(project.path(), root.path());
+        assert!(root.path().join(".tcli").is_dir());
+    }
+
+    #[test]
+    fn new_opens_project_from_manifest_path() {
+        let root = tempfile::tempdir().unwrap();
+        write_project(root.path());
+
+        let project = ProjectPath::new(&root.path().join("Thunderstore.toml")).unwrap();
+        assert_eq!(project.path(), root.path());
+    }
+
+    #[test]
+    fn locate_discovers_default_manifest_from_nested_directory() {
+        let root = tempfile::tempdir().unwrap();
+        write_project(root.path());
+
+        let nested = root.path().join("BepInEx").join("plugins");
+        fs::create_dir_all(&nested).unwrap();
+
+        // Commands such as `add` and `clean` are given the default manifest path when run without
+        // `--project-path`.
+        let project = ProjectPath::locate(Path::new(DEFAULT_MANIFEST), &nested).unwrap();
+        assert_eq!(project.path(), root.path());
+    }
+
+    #[test]
+    fn locate_does_not_discover_explicit_path() {
+        let root = tempfile::tempdir().unwrap();
+        write_project(root.path());
+
+        let missing = root.path().join("sub").join("Thunderstore.toml");
+        let result = ProjectPath::locate(&missing, root.path());
+        assert!(matches!(result, Err(Error::NoProjectFile(_))));
+    }
+
+    #[test]
+    fn open_unchecked_resolves_manifest_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let manifest_path = root.path().join("Thunderstore.toml");
+        fs::write(&manifest_path, "").unwrap();
+
+        assert_eq!(
+            ProjectPath::open_unchecked(&manifest_path).path(),
+            root.path()
+        );
+        assert_eq!(ProjectPath::open_unchecked(root.path()).path(), root.path());
+    }
+
+    #[test]
+    fn validate_requires_manifest_and_tcli_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let project = ProjectPath::open_unchecked(root.path());
+
+        assert!(matches!(project.validate(), Err(Error::NoProjectFile(_))));
+
+        fs::write(root.path().join("Thunderstore.toml"), "").unwrap();
+        assert!(matches!(project.validate(), Err(Error::NoProjectFile(_))));
+
+        fs::create_dir(root.path().join(".tcli")).unwrap();
+        assert!(project.validate().is_ok());
+    }
+
+    #[test]
+    fn create_new_overwrites_existing_project() {
+        let root = tempfile::tempdir().unwrap();
+        let manifest_path = root.path().join("Thunderstore.toml");
+
+        // The profile manifest is shorter than the dev manifest, so any leftover bytes of the
+        // first manifest would corrupt the second.
+        create_new(
+            &manifest_path,
+            false,
+            ProjectKind::Dev,
+            ProjectOverrides::new(),
+        )
+        .unwrap();
+        create_new(
+            &manifest_path,
+            true,
+            ProjectKind::Profile,
+            ProjectOverrides::new(),
+        )
+        .unwrap();
+
+        let manifest = ProjectManifest::read_from_file(&manifest_path).unwrap();
+        assert!(manifest.package.is_none());
+        assert_eq!(
+            fs::read_to_string(&manifest_path).unwrap(),
+            toml::to_string_pretty(&ProjectManifest::default_profile_project()).unwrap()
+        );
+    }
+
+    #[test]
+    fn create_new_refuses_existing_project() {
+        let root = tempfile::tempdir().unwrap();
+        let manifest_path = root.path().join("Thunderstore.toml");
+
+        create_new(
+            &manifest_path,
+            false,
+            ProjectKind::Dev,
+            ProjectOverrides::new(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            create_new(
+                &manifest_path,
+                false,
+                ProjectKind::Dev,
+                ProjectOverrides::new()
+            ),
+            Err(Error::ProjectAlreadyExists(_))
+        ));
+    }
+
+    fn build_reproducible(dir: &Path, files: &[&str]) -> Vec<u8> {
+        let manifest_path = dir.join("Thunderstore.toml");
+        let overrides = ProjectOverrides::new().reproducible_override(Some(true));
+        create_new(&manifest_path, false, ProjectKind::Dev, overrides).unwrap();
 
-    zip.finish()?;
+        for file in files {
+            let path = dir.join("dist").join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+
+        let manifest = ProjectManifest::read_from_file(&manifest_path).unwrap();
+        let output = build(&manifest, BuildEmit::Zip).unwrap();
+
+        fs::read(output.archive.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reproducible_builds_are_identical() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+
+        // Files are created in a different order, so the filesystem may list them differently.
+        let first = build_reproducible(first.path(), &["a.dll", "config/b.cfg", "z.txt"]);
+        let second = build_reproducible(second.path(), &["z.txt", "config/b.cfg", "a.dll"]);
+
+        assert!(first == second, "reproducible builds differ");
+    }
+
+    #[test]
+    fn build_uses_forward_slash_entry_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = build_reproducible(dir.path(), &["plugins/nested/mod.dll"]);
+
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort();
+
+        assert_eq!(
+            names,
+            [
+                "README.md",
+                "icon.png",
+                "manifest.json",
+                "plugins/",
+                "plugins/nested/",
+                "plugins/nested/mod.dll",
+            ]
+        );
+    }
 
-    Ok(output_path)
+    #[test]
+    fn build_skips_excluded_files_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Thunderstore.toml");
+        create_new(
+            &manifest_path,
+            false,
+            ProjectKind::Dev,
+            ProjectOverrides::new(),
+        )
+        .unwrap();
+
+        for file in [
+            "mod.dll",
+            "notes.md",
+            "plugins/changes.md",
+            "cache/a.bin",
+            "cache/nested/b.bin",
+        ] {
+            let path = dir.path().join("dist").join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+
+        let mut manifest = ProjectManifest::read_from_file(&manifest_path).unwrap();
+        manifest.build.as_mut().unwrap().exclude = vec!["*.md".to_string(), "cache".to_string()];
+        let output = build(&manifest, BuildEmit::Zip).unwrap();
+
+        let archive = zip::ZipArchive::new(File::open(output.archive.unwrap()).unwrap()).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort();
+
+        // The readme is copied from the project directory rather than the copy source, so it's
+        // never excluded.
+        assert_eq!(
+            names,
+            [
+                "README.md",
+                "icon.png",
+                "manifest.json",
+                "mod.dll",
+                "plugins/"
+            ]
+        );
+    }
+
+    #[test]
+    fn build_rejects_blank_readme() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Thunderstore.toml");
+        create_new(
+            &manifest_path,
+            false,
+            ProjectKind::Dev,
+            ProjectOverrides::new(),
+        )
+        .unwrap();
+        fs::write(dir.path().join("README.md"), " \n\t\n").unwrap();
+
+        let manifest = ProjectManifest::read_from_file(&manifest_path).unwrap();
+
+        assert!(matches!(
+            build(&manifest, BuildEmit::Zip),
+            Err(Error::EmptyReadme(_))
+        ));
+    }
+
+    #[test]
+    fn build_directory_matches_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("Thunderstore.toml");
+        create_new(
+            &manifest_path,
+            false,
+            ProjectKind::Dev,
+            ProjectOverrides::new(),
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("dist")).unwrap();
+
+        let manifest = ProjectManifest::read_from_file(&manifest_path).unwrap();
+        let output = build(&manifest, BuildEmit::Both).unwrap();
+
+        let directory = output.directory.unwrap();
+        let archive = File::open(output.archive.unwrap()).unwrap();
+        let mut archive = zip::ZipArchive::new(archive).unwrap();
+
+        for name in ["manifest.json", "icon.png", "README.md"] {
+            let mut contents = Vec::new();
+            std::io::copy(&mut archive.by_name(name).unwrap(), &mut contents).unwrap();
+
+            assert_eq!(contents, fs::read(directory.join(name)).unwrap(), "{name}");
+        }
+    }
+
+    #[test]
+    fn explicit_path_is_not_discovered() {
+        let root = tempfile::tempdir().unwrap();
+        write_project(root.path());
+
+        let nested = root.path().join("nested");
+        fs::create_dir_all(&nested).unwrap();
+
+        assert!(matches!(
+            ProjectPath::new(&nested),
+            Err(Error::NoProjectFile(x)) if x == nested
+        ));
+        assert!(matches!(
+            ProjectPath::new(&nested.join("Thunderstore.toml")),
+            Err(Error::NoProjectFile(_))
+        ));
+    }
+
+    /// Create a profile project with the given packages in its lockfile, and a directory within
+    /// `project_state` for each of the installed packages.
+    fn write_profile(dir: &Path, locked: &[&str], installed: &[&str]) -> ProjectPath {
+        write_project(dir);
+
+        let packages = locked
+            .iter()
+            .map(|x| Package {
+                identifier: x.parse().unwrap(),
+                source: PackageSource::Local(PathBuf::from(format!("{x}.zip"))),
+                dependencies: vec![],
+            })
+            .collect::<Vec<_>>();
+        let mut lockfile = LockFile::new(&dir.join("Thunderstore.lock"));
+        lockfile.merge(&packages);
+        lockfile.commit().unwrap();
+
+        for package in installed {
+            let path = dir.join("project_state").join(package);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("manifest.json"), "{}").unwrap();
+        }
+
+        ProjectPath::open_unchecked(dir)
+    }
+
+    #[test]
+    fn clean_dry_run_lists_without_removing() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = write_profile(
+            dir.path(),
+            &["Author-Mod-1.0.0"],
+            &["Author-Mod-1.0.0", "Author-Stale-1.0.0"],
+        );
+
+        let untracked = dir.path().join("project_state").join("Author-Stale-1.0.0");
+        assert_eq!(
+            clean(&project, true).unwrap(),
+            std::slice::from_ref(&untracked)
+        );
+        assert!(untracked.is_dir());
+    }
+
+    #[test]
+    fn clean_removes_only_untracked_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = write_profile(
+            dir.path(),
+            &["Author-Mod-1.0.0"],
+            &["Author-Mod-1.0.0", "Author-Stale-1.0.0"],
+        );
+
+        let project_state = dir.path().join("project_state");
+        let untracked = project_state.join("Author-Stale-1.0.0");
+        assert_eq!(
+            clean(&project, false).unwrap(),
+            std::slice::from_ref(&untracked)
+        );
+        assert!(!untracked.exists());
+        assert!(project_state.join("Author-Mod-1.0.0").is_dir());
+    }
+
+    #[test]
+    fn uninstall_all_keeps_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = write_profile(
+            dir.path(),
+            &["Author-Mod-1.0.0", "Author-Lib-2.0.0"],
+            &["Author-Mod-1.0.0", "Author-Lib-2.0.0"],
+        );
+
+        let manifest_path = dir.path().join("Thunderstore.toml");
+        let manifest = toml::to_string_pretty(&ProjectManifest::default_dev_project()).unwrap();
+        fs::write(&manifest_path, &manifest).unwrap();
+
+        assert_eq!(uninstall_all(&project).unwrap(), 2);
+
+        let lockfile = LockFile::open_or_new(&dir.path().join("Thunderstore.lock")).unwrap();
+        assert!(lockfile.packages.is_empty());
+        assert!(!dir.path().join("project_state").exists());
+        assert_eq!(fs::read_to_string(&manifest_path).unwrap(), manifest);
+    }
 }
@@ -9,6 +9,7 @@ pub struct ProjectOverrides {
     pub(in crate::project) version: Option<Version>,
     pub(in crate::project) output_dir: Option<PathBuf>,
     pub(in crate::project) repository: Option<String>,
+    pub(in crate::project) reproducible: Option<bool>,
 }
 
 impl ProjectOverrides {
@@ -50,4 +51,11 @@ impl ProjectOverrides {
             ..self
         }
     }
+
+    pub fn reproducible_override(self, reproducible: Option<bool>) -> Self {
+        Self {
+            reproducible: reproducible.or(self.reproducible),
+            ..self
+        }
+    }
 }
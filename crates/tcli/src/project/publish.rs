@@ -1,14 +1,19 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::error::Error;
 use crate::project::manifest::ProjectManifest;
+use crate::project::{BuildEmit, ProjectKind};
 use crate::ts::experimental::models::publish::PackageSubmissionMetadata;
 use crate::ts::experimental::publish;
 
 pub async fn publish(
-    manifest: &ProjectManifest,
+    manifest: ProjectManifest,
     archive_path: Option<PathBuf>,
 ) -> Result<(), Error> {
+    manifest.ensure_kind(ProjectKind::Dev)?;
+    let manifest = Arc::new(manifest);
+
     let package = manifest
         .package
         .as_ref()
@@ -16,7 +21,10 @@ pub async fn publish(
 
     let archive_path = match archive_path {
         Some(path) => path,
-        None => super::build(manifest)?,
+        None => super::build_blocking(Arc::clone(&manifest), BuildEmit::Zip)
+            .await?
+            .archive
+            .expect("A zip archive was requested from the build"),
     };
 
     let usermedia = publish::upload_file(archive_path).await?;
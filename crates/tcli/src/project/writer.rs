@@ -0,0 +1,133 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::error::{Error, IoResultToTcli};
+
+pub(super) type WriteFn<'a> = dyn FnMut(&mut dyn Write) -> Result<(), Error> + 'a;
+
+/// A destination for the assembled contents of a package.
+pub(super) trait PackageWriter {
+    fn add_directory(&mut self, path: &Path) -> Result<(), Error>;
+
+    fn add_file(&mut self, path: &Path, contents: &mut dyn Read) -> Result<(), Error>;
+
+    /// Add a file whose contents are generated by the given function as they're written, rather
+    /// than read from a source.
+    fn write_file(&mut self, path: &Path, write: &mut WriteFn) -> Result<(), Error>;
+
+    fn finish(self: Box<Self>) -> Result<(), Error>;
+}
+
+/// Writes the package as a zip archive, ready to be published.
+pub(super) struct ZipPackageWriter {
+    zip: ZipWriter<File>,
+    options: FileOptions,
+}
+
+impl ZipPackageWriter {
+    pub fn create(path: &Path, options: FileOptions) -> Result<Self, Error> {
+        let file = File::options()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .map_fs_error(path)?;
+
+        Ok(ZipPackageWriter {
+            zip: ZipWriter::new(file),
+            options,
+        })
+    }
+}
+
+impl PackageWriter for ZipPackageWriter {
+    fn add_directory(&mut self, path: &Path) -> Result<(), Error> {
+        self.zip.add_directory(entry_name(path), self.options)?;
+        Ok(())
+    }
+
+    fn add_file(&mut self, path: &Path, contents: &mut dyn Read) -> Result<(), Error> {
+        self.zip.start_file(entry_name(path), self.options)?;
+        io::copy(contents, &mut self.zip)?;
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, write: &mut WriteFn) -> Result<(), Error> {
+        self.zip.start_file(entry_name(path), self.options)?;
+        write(&mut self.zip)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Error> {
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+/// The name of the archive entry at the given path. Zip entries are always separated with forward
+/// slashes, so archives built on Windows are identical to those built elsewhere.
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|x| x.as_os_str().to_string_lossy())
+        .join("/")
+}
+
+/// Writes the package as a loose directory tree with exactly the contents of the archive.
+pub(super) struct DirPackageWriter {
+    root: PathBuf,
+}
+
+impl DirPackageWriter {
+    /// Create the output directory, removing the output of any previous build.
+    pub fn create(root: &Path) -> Result<Self, Error> {
+        match fs::remove_dir_all(root) {
+            Ok(_) => (),
+            Err(e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(Error::FileIoError(root.to_path_buf(), e)),
+        }
+
+        fs::create_dir_all(root).map_fs_error(root)?;
+
+        Ok(DirPackageWriter {
+            root: root.to_path_buf(),
+        })
+    }
+}
+
+impl PackageWriter for DirPackageWriter {
+    fn add_directory(&mut self, path: &Path) -> Result<(), Error> {
+        let path = self.root.join(path);
+        fs::create_dir_all(&path).map_fs_error(&path)
+    }
+
+    fn add_file(&mut self, path: &Path, contents: &mut dyn Read) -> Result<(), Error> {
+        let path = self.root.join(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_fs_error(parent)?;
+        }
+
+        io::copy(contents, &mut File::create(&path).map_fs_error(&path)?).map_fs_error(&path)?;
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, write: &mut WriteFn) -> Result<(), Error> {
+        let path = self.root.join(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_fs_error(parent)?;
+        }
+
+        let mut file = BufWriter::new(File::create(&path).map_fs_error(&path)?);
+        write(&mut file)?;
+        file.flush().map_fs_error(&path)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -1,12 +1,12 @@
 use crate::error::Error;
-use crate::ts::experimental::models::package::{PackageListing, PackageVersion};
+use crate::ts::experimental::models::package::{PackageMetadata, PackageVersion};
 use crate::ts::version::Version;
-use crate::ts::{CLIENT, EX};
+use crate::ts::{SendTraced, CLIENT, EX};
 
-pub async fn get_metadata(author: &str, name: &str) -> Result<PackageListing, Error> {
+pub async fn get_metadata(author: &str, name: &str) -> Result<PackageMetadata, Error> {
     Ok(CLIENT
         .get(format!("{EX}/package/{author}/{name}/"))
-        .send()
+        .send_traced()
         .await?
         .error_for_status()?
         .json()
@@ -20,9 +20,19 @@ pub async fn get_version_metadata(
 ) -> Result<PackageVersion, Error> {
     Ok(CLIENT
         .get(format!("{EX}/package/{author}/{name}/{version}/"))
-        .send()
+        .send_traced()
         .await?
         .error_for_status()?
         .json()
         .await?)
 }
+
+/// Get the size of a package's archive from the download's headers, without downloading it.
+pub async fn get_file_size(download_url: &str) -> Result<Option<u64>, Error> {
+    Ok(CLIENT
+        .head(download_url)
+        .send_traced()
+        .await?
+        .error_for_status()?
+        .content_length())
+}
@@ -2,9 +2,14 @@ use std::io::{Read, Seek, SeekFrom};
 #[cfg(target_os = "windows")]
 use std::os::windows::prelude::OpenOptionsExt;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
+use colored::Colorize;
+use futures::TryStreamExt;
 use indicatif::ProgressBar;
 use md5::digest::FixedOutput;
 use md5::Md5;
@@ -13,9 +18,14 @@ use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
 use crate::error::{Error, IoResultToTcli, ReqwestToTcli};
 use crate::ts::experimental::models::publish::*;
-use crate::ts::{AUTH, CLIENT, EX};
+use crate::ts::{SendTraced, AUTH, CLIENT, EX};
 use crate::ui::PROGRESS_STYLE;
 
+/// How many times a failed chunk upload is retried before the publish is aborted.
+const UPLOAD_RETRIES: u32 = 3;
+/// The delay before the first retry of a chunk, doubled for each subsequent retry.
+const UPLOAD_RETRY_DELAY: Duration = Duration::from_secs(2);
+
 pub async fn usermedia_initiate(
     params: &UserMediaInitiateUploadParams,
 ) -> Result<UserMediaInitiateUploadResponse, Error> {
@@ -26,7 +36,7 @@ pub async fn usermedia_initiate(
             AUTH.get().ok_or(Error::MissingAuthToken)?,
         )
         .json(params)
-        .send()
+        .send_traced()
         .await?
         .error_for_status_tcli()
         .await?
@@ -45,7 +55,7 @@ pub async fn usermedia_finish(
             AUTH.get().ok_or(Error::MissingAuthToken)?,
         )
         .json(params)
-        .send()
+        .send_traced()
         .await?
         .error_for_status_tcli()
         .await?;
@@ -59,7 +69,7 @@ pub async fn usermedia_abort(uuid: String) -> Result<(), Error> {
             header::AUTHORIZATION,
             AUTH.get().ok_or(Error::MissingAuthToken)?,
         )
-        .send()
+        .send_traced()
         .await?
         .error_for_status_tcli()
         .await?;
@@ -100,40 +110,27 @@ pub async fn upload_file(path: impl AsRef<Path>) -> Result<UserMedia, Error> {
     let tags_result: Result<Vec<CompletedPart>, Error> =
         futures::future::try_join_all(initiate_response.upload_urls.into_iter().map(
             |url| async move {
-                let mut file = open_options.open(path).map_fs_error(path)?;
-                file.seek(SeekFrom::Start(url.offset))?;
-
-                let mut md5 = Md5::default();
-                std::io::copy(&mut file.try_clone().unwrap().take(url.length), &mut md5)?;
-                let md5 = BASE64_STANDARD.encode(md5.finalize_fixed());
-
-                file.seek(SeekFrom::Start(url.offset))?;
-
-                let data_stream = tokio::fs::File::from_std(file).take(url.length);
-                let with_progress = progress_bar.wrap_async_read(data_stream);
-
-                let upload_response = CLIENT
-                    .put(url.url)
-                    .header(header::CONTENT_LENGTH, url.length)
-                    .header("Content-MD5", md5)
-                    .body(Body::wrap_stream(tokio_util::io::ReaderStream::new(
-                        with_progress,
-                    )))
-                    .send()
-                    .await?
-                    .error_for_status_tcli()
-                    .await?;
-                let etag = upload_response
-                    .headers()
-                    .get("ETag")
-                    .expect("Expected ETag in upload response")
-                    .to_str()
-                    .expect("ETag was not a valid string");
-
-                Ok(CompletedPart {
-                    etag: etag.to_string(),
-                    part_number: url.part_number,
-                })
+                let mut attempt = 0;
+
+                // Parts are uploaded independently, so a failed part is retried on its own without
+                // restarting the parts which have already been uploaded.
+                loop {
+                    match upload_part(path, open_options, &url, progress_bar).await {
+                        Err(e) if attempt < UPLOAD_RETRIES && is_retryable(&e) => {
+                            let delay = UPLOAD_RETRY_DELAY * 2u32.pow(attempt);
+                            attempt += 1;
+
+                            progress_bar.println(format!(
+                                "{} chunk {} failed, retrying in {}s ({attempt}/{UPLOAD_RETRIES}): {e}",
+                                "[!]".bright_yellow(),
+                                url.part_number,
+                                delay.as_secs(),
+                            ));
+                            tokio::time::sleep(delay).await;
+                        }
+                        result => break result,
+                    }
+                }
             },
         ))
         .await;
@@ -161,6 +158,76 @@ pub async fn upload_file(path: impl AsRef<Path>) -> Result<UserMedia, Error> {
     Ok(usermedia)
 }
 
+/// Connection failures and server errors may succeed when retried, client errors won't.
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::NetworkError(_) => true,
+        Error::ApiError { source, .. } => source.status().is_none_or(|x| x.is_server_error()),
+        _ => false,
+    }
+}
+
+/// Upload a single part of a file, returning the part's ETag. Progress made by a failed upload
+/// is removed from the progress bar so that it can be retried.
+async fn upload_part(
+    path: &Path,
+    open_options: &std::fs::OpenOptions,
+    url: &UploadPartUrl,
+    progress_bar: &ProgressBar,
+) -> Result<CompletedPart, Error> {
+    let mut file = open_options.open(path).map_fs_error(path)?;
+    file.seek(SeekFrom::Start(url.offset))?;
+
+    let mut md5 = Md5::default();
+    std::io::copy(&mut file.try_clone().unwrap().take(url.length), &mut md5)?;
+    let md5 = BASE64_STANDARD.encode(md5.finalize_fixed());
+
+    file.seek(SeekFrom::Start(url.offset))?;
+
+    let sent = Arc::new(AtomicU64::new(0));
+    let data_stream = tokio::fs::File::from_std(file).take(url.length);
+    let with_progress =
+        tokio_util::io::ReaderStream::new(progress_bar.wrap_async_read(data_stream)).inspect_ok({
+            let sent = sent.clone();
+            move |chunk| {
+                sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }
+        });
+
+    let upload = async {
+        CLIENT
+            .put(&url.url)
+            .header(header::CONTENT_LENGTH, url.length)
+            .header("Content-MD5", md5)
+            .body(Body::wrap_stream(with_progress))
+            .send_traced()
+            .await?
+            .error_for_status_tcli()
+            .await
+    };
+
+    let upload_response = match upload.await {
+        Ok(response) => response,
+        Err(e) => {
+            let sent = sent.load(Ordering::Relaxed);
+            progress_bar.set_position(progress_bar.position().saturating_sub(sent));
+            return Err(e);
+        }
+    };
+
+    let etag = upload_response
+        .headers()
+        .get("ETag")
+        .expect("Expected ETag in upload response")
+        .to_str()
+        .expect("ETag was not a valid string");
+
+    Ok(CompletedPart {
+        etag: etag.to_string(),
+        part_number: url.part_number,
+    })
+}
+
 pub async fn package_submit(
     params: &PackageSubmissionMetadata,
 ) -> Result<PackageSubmissionResult, Error> {
@@ -171,7 +238,7 @@ pub async fn package_submit(
             AUTH.get().ok_or(Error::MissingAuthToken)?,
         )
         .json(params)
-        .send()
+        .send_traced()
         .await?
         .error_for_status_tcli()
         .await?
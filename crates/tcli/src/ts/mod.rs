@@ -1,8 +1,14 @@
 use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use async_trait::async_trait;
 use once_cell::sync::{Lazy, OnceCell};
 use reqwest::header::HeaderValue;
-use reqwest::Client;
+use reqwest::{Client, Method, Request, RequestBuilder, Response};
+
+use crate::config::Vars;
+use crate::util::SecretString;
 
 pub mod experimental;
 pub mod package_manifest;
@@ -30,15 +36,16 @@ impl Display for RepositoryUrl {
     }
 }
 
-pub fn init_repository(repo: &str, auth_token: Option<&str>) {
+pub fn init_repository(repo: &str, auth_token: Option<&SecretString>) {
     CM.0.set(format!("{repo}/c")).unwrap();
     V1.0.set(format!("{repo}/api/v1")).unwrap();
     EX.0.set(format!("{repo}/api/experimental")).unwrap();
     if let Some(auth) = auth_token {
-        AUTH.set(
-            HeaderValue::try_from(format!("Bearer {auth}")).expect("Invalid auth token format"),
-        )
-        .unwrap();
+        let mut header = HeaderValue::try_from(format!("Bearer {}", auth.expose()))
+            .expect("Invalid auth token format");
+        header.set_sensitive(true);
+
+        AUTH.set(header).unwrap();
     }
 }
 
@@ -47,6 +54,99 @@ pub(in crate::ts) static V1: RepositoryUrl = RepositoryUrl::new();
 pub(in crate::ts) static EX: RepositoryUrl = RepositoryUrl::new();
 pub(in crate::ts) static AUTH: OnceCell<HeaderValue> = OnceCell::new();
 
+static TRACE_NETWORK: AtomicBool = AtomicBool::new(false);
+
+/// Log the method, url, status and duration of every request sent through
+/// [`SendTraced::send_traced`] to stderr.
+pub fn enable_network_trace() {
+    TRACE_NETWORK.store(true, Ordering::Relaxed);
+}
+
+/// How many times idempotent requests are retried after a network or server error. Set with
+/// TCLI_NETWORK_RETRIES, where 0 disables retries.
+static NETWORK_RETRIES: Lazy<u32> = Lazy::new(|| {
+    Vars::NetworkRetries
+        .into_var()
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(2)
+});
+
+#[async_trait]
+pub(crate) trait SendTraced {
+    /// Send the request, tracing it if network tracing is enabled. GET and HEAD requests are
+    /// retried with exponential backoff when they fail with a network or server error.
+    async fn send_traced(self) -> reqwest::Result<Response>;
+}
+
+#[async_trait]
+impl SendTraced for RequestBuilder {
+    async fn send_traced(self) -> reqwest::Result<Response> {
+        let (client, request) = self.build_split();
+        let mut request = request?;
+
+        let retries = match *request.method() {
+            Method::GET | Method::HEAD => *NETWORK_RETRIES,
+            _ => 0,
+        };
+
+        let mut attempt = 0;
+
+        loop {
+            // Requests with a streamed body can't be cloned, and so can't be retried.
+            let retry = request.try_clone().filter(|_| attempt < retries);
+            let response = execute_traced(&client, request).await;
+
+            request = match (&response, retry) {
+                (Err(e), Some(retry)) if e.is_connect() || e.is_timeout() || e.is_request() => {
+                    retry
+                }
+                (Ok(x), Some(retry)) if x.status().is_server_error() => retry,
+                _ => return response,
+            };
+
+            tokio::time::sleep(backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Exponential backoff starting at half a second, with up to a quarter second of jitter so that
+/// concurrent requests don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let jitter = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.subsec_millis() % 250);
+
+    Duration::from_millis(500 * 2u64.pow(attempt) + u64::from(jitter))
+}
+
+async fn execute_traced(client: &Client, request: Request) -> reqwest::Result<Response> {
+    if !TRACE_NETWORK.load(Ordering::Relaxed) {
+        return client.execute(request).await;
+    }
+
+    // Only the method and url are logged, headers carry the auth token. The query is dropped
+    // as presigned upload urls carry their credentials within it.
+    let method = request.method().clone();
+    let mut url = request.url().clone();
+    url.set_query(None);
+
+    let started = Instant::now();
+    let response = client.execute(request).await;
+    let elapsed = started.elapsed().as_millis();
+
+    match &response {
+        Ok(response) => eprintln!(
+            "[net] {method} {url} -> {} in {elapsed}ms",
+            response.status()
+        ),
+        Err(_) => eprintln!("[net] {method} {url} -> failed in {elapsed}ms"),
+    }
+
+    response
+}
+
 pub(crate) static CLIENT: Lazy<Client> = Lazy::new(|| {
     Client::builder()
         .user_agent(concat!("thunderstore-cli/", env!("CARGO_PKG_VERSION")))
@@ -5,7 +5,8 @@ use crate::ts::version::{Version, VersionParseError};
 
 pub mod ser;
 
-#[derive(Clone, Debug, PartialEq)]
+/// References are ordered by namespace, then name, then version.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PackageReference {
     pub namespace: String,
     pub name: String,
@@ -29,7 +30,7 @@ impl PackageReference {
         fullname: impl AsRef<str>,
         version: Version,
     ) -> Result<Self, PackageReferenceParseError> {
-        let (name, namespace) =
+        let (namespace, name) =
             fullname
                 .as_ref()
                 .rsplit_once('-')
@@ -78,8 +79,125 @@ impl FromStr for PackageReference {
     }
 }
 
+/// A package reference with an optional version, in the format 'namespace-name(-version)'.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoosePackageReference {
+    pub namespace: String,
+    pub name: String,
+    pub version: Option<Version>,
+}
+
+impl FromStr for LoosePackageReference {
+    type Err = PackageReferenceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split('-').count() {
+            2 => {
+                let (namespace, name) = s.split_once('-').unwrap();
+
+                Ok(LoosePackageReference {
+                    namespace: namespace.to_string(),
+                    name: name.to_string(),
+                    version: None,
+                })
+            }
+            3 => {
+                let reference = s.parse::<PackageReference>()?;
+
+                Ok(LoosePackageReference {
+                    namespace: reference.namespace,
+                    name: reference.name,
+                    version: Some(reference.version),
+                })
+            }
+            got => Err(PackageReferenceParseError::NumSections {
+                expected: 3,
+                got: got - 1,
+            }),
+        }
+    }
+}
+
 impl Display for PackageReference {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}-{}-{}", self.namespace, self.name, self.version)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trips() {
+        let reference = "Author-Mod_Name-1.2.3".parse::<PackageReference>().unwrap();
+
+        assert_eq!(reference.namespace, "Author");
+        assert_eq!(reference.name, "Mod_Name");
+        assert_eq!(reference.version, Version::new(1, 2, 3));
+        assert_eq!(reference.to_string(), "Author-Mod_Name-1.2.3");
+        assert_eq!(
+            reference.to_string().parse::<PackageReference>().unwrap(),
+            reference
+        );
+    }
+
+    #[test]
+    fn parse_rejects_missing_sections() {
+        assert!(matches!(
+            "Author-1.2.3".parse::<PackageReference>(),
+            Err(PackageReferenceParseError::NumSections {
+                expected: 3,
+                got: 1
+            })
+        ));
+        assert!(matches!(
+            "Author-Mod-latest".parse::<PackageReference>(),
+            Err(PackageReferenceParseError::VersionParseFail(_))
+        ));
+    }
+
+    #[test]
+    fn from_fullname_version_splits_namespace_then_name() {
+        let reference =
+            PackageReference::from_fullname_version("Author-Mod", Version::new(1, 0, 0)).unwrap();
+
+        assert_eq!(reference.namespace, "Author");
+        assert_eq!(reference.name, "Mod");
+        assert_eq!(reference.to_loose_ident_string(), "Author-Mod");
+    }
+
+    #[test]
+    fn loose_parse_accepts_optional_version() {
+        let loose = "Author-Mod".parse::<LoosePackageReference>().unwrap();
+        assert_eq!(loose.namespace, "Author");
+        assert_eq!(loose.name, "Mod");
+        assert_eq!(loose.version, None);
+
+        let loose = "Author-Mod-1.0.0".parse::<LoosePackageReference>().unwrap();
+        assert_eq!(loose.version, Some(Version::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn table_round_trips() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Dependencies {
+            #[serde(with = "ser::table")]
+            dependencies: Vec<PackageReference>,
+        }
+
+        let references = vec![
+            "Author-Mod-1.0.0".parse().unwrap(),
+            "Other-Library-2.3.4".parse().unwrap(),
+        ];
+        let toml = toml::to_string(&Dependencies {
+            dependencies: references.clone(),
+        })
+        .unwrap();
+
+        let mut parsed = toml::from_str::<Dependencies>(&toml).unwrap().dependencies;
+        parsed.sort();
+
+        assert_eq!(parsed, references);
+    }
+}
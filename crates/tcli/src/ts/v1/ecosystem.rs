@@ -1,13 +1,102 @@
+use serde::{Deserialize, Serialize};
+
 use crate::error::Error;
 use crate::ts::v1::models::ecosystem::EcosystemSchema;
-use crate::ts::CLIENT;
+use crate::ts::{SendTraced, CLIENT};
+
+/// The release channel the ecosystem schema is downloaded from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchemaChannel {
+    /// The schema version this release of tcli was built against.
+    #[default]
+    Stable,
+    /// The newest schema published by Thunderstore, which may contain breaking changes.
+    DevLatest,
+}
+
+impl SchemaChannel {
+    /// The name of this channel as used within the config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaChannel::Stable => "stable",
+            SchemaChannel::DevLatest => "dev-latest",
+        }
+    }
+
+    pub fn url(&self) -> &'static str {
+        match self {
+            SchemaChannel::Stable => {
+                "https://gcdn.thunderstore.io/static/dev/schema/ecosystem-schema.0.0.11.json"
+            }
+            SchemaChannel::DevLatest => {
+                "https://thunderstore.io/api/experimental/schema/dev/latest/"
+            }
+        }
+    }
+}
+
+/// Download the ecosystem schema, retrying once if the response is cut off.
+pub async fn get_schema(channel: SchemaChannel) -> Result<EcosystemSchema, Error> {
+    get_schema_from(channel.url()).await
+}
+
+async fn get_schema_from(url: &str) -> Result<EcosystemSchema, Error> {
+    match download_schema(url).await {
+        Err(Error::TruncatedResponse(_)) => download_schema(url).await,
+        result => result,
+    }
+}
 
-pub async fn get_schema() -> Result<EcosystemSchema, Error> {
-    Ok(CLIENT
-        .get("https://gcdn.thunderstore.io/static/dev/schema/ecosystem-schema.0.0.11.json")
-        .send()
+async fn download_schema(url: &str) -> Result<EcosystemSchema, Error> {
+    let body = CLIENT
+        .get(url)
+        .send_traced()
         .await?
         .error_for_status()?
-        .json()
-        .await?)
+        .bytes()
+        .await
+        .map_err(|e| match e.is_body() {
+            true => Error::TruncatedResponse("ecosystem schema"),
+            false => Error::NetworkError(e),
+        })?;
+
+    serde_json::from_slice(&body).map_err(|e| match e.is_eof() {
+        true => Error::TruncatedResponse("ecosystem schema"),
+        false => Error::JsonParserError(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_server::{response, serve};
+
+    const SCHEMA: &[u8] = br#"{ "schemaVersion": "0.0.11", "games": {}, "communities": {} }"#;
+
+    #[tokio::test]
+    async fn get_schema_retries_truncated_response() {
+        let address = serve(vec![
+            response(SCHEMA, SCHEMA.len() / 2),
+            response(SCHEMA, SCHEMA.len()),
+        ])
+        .await;
+
+        let schema = get_schema_from(&format!("{address}/schema.json"))
+            .await
+            .unwrap();
+        assert!(schema.games.is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_schema_fails_when_retry_is_truncated() {
+        let address = serve(vec![
+            response(SCHEMA, SCHEMA.len() / 2),
+            response(SCHEMA, SCHEMA.len() / 2),
+        ])
+        .await;
+
+        let result = get_schema_from(&format!("{address}/schema.json")).await;
+        assert!(matches!(result, Err(Error::TruncatedResponse(_))));
+    }
 }
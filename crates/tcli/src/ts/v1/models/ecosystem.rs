@@ -55,6 +55,32 @@ pub enum GameDefPlatform {
     Other,
 }
 
+impl GameDefPlatform {
+    /// Every platform name used within the ecosystem schema.
+    pub const NAMES: [&'static str; 7] = [
+        "egs",
+        "xbox-game-pass",
+        "origin",
+        "steam",
+        "steam-direct",
+        "oculus",
+        "other",
+    ];
+
+    /// The name of this platform as used within the ecosystem schema.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameDefPlatform::EpicGames { .. } => "egs",
+            GameDefPlatform::GamePass { .. } => "xbox-game-pass",
+            GameDefPlatform::Origin { .. } => "origin",
+            GameDefPlatform::Steam { .. } => "steam",
+            GameDefPlatform::SteamDirect { .. } => "steam-direct",
+            GameDefPlatform::Oculus => "oculus",
+            GameDefPlatform::Other => "other",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct GameDefR2MM {
@@ -0,0 +1,41 @@
+use std::io::{self, Write};
+
+use crate::error::Error;
+
+/// Ask the user a yes / no question on the terminal, defaulting to no. Prompts are written to
+/// stderr, keeping stdout for the command's output.
+pub fn confirm(question: &str) -> Result<bool, Error> {
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Ask the user to pick one of the options from a numbered list on the terminal, returning the
+/// index of the chosen option. Returns none if the user enters nothing.
+pub fn select(question: &str, options: &[String]) -> Result<Option<usize>, Error> {
+    eprintln!("{question}");
+    for (i, option) in options.iter().enumerate() {
+        eprintln!("{:>3}. {option}", i + 1);
+    }
+
+    loop {
+        eprint!("Enter a number [1-{}]: ", options.len());
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+
+        if answer.trim().is_empty() {
+            return Ok(None);
+        }
+
+        match answer.trim().parse::<usize>() {
+            Ok(n) if (1..=options.len()).contains(&n) => return Ok(Some(n - 1)),
+            _ => eprintln!("'{}' is not one of the options.", answer.trim()),
+        }
+    }
+}
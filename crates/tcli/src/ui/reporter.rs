@@ -1,9 +1,46 @@
 use indicatif::{MultiProgress, ProgressBar};
+use serde::Serialize;
 
+use crate::package::report::InstallReport;
+use crate::package::resolver::InstallPlan;
 use crate::ui::PROGRESS_STYLE;
 
 pub trait Reporter {
     fn create_progress(&self) -> Box<dyn Progress>;
+
+    /// Report a significant step of the command. Human readable reporters already describe
+    /// these through their progress output, so they ignore them by default.
+    fn event(&self, _event: &Event) {}
+}
+
+/// A machine readable description of a step within a command's lifecycle.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    ResolveStarted {
+        packages: usize,
+    },
+    ResolveFinished {
+        packages: usize,
+        elapsed_ms: u128,
+    },
+    Plan {
+        #[serde(flatten)]
+        plan: InstallPlan,
+    },
+    PackageInstalled {
+        package: String,
+        downloaded_bytes: u64,
+        installed_files: u64,
+        installed_bytes: u64,
+    },
+    Summary {
+        #[serde(flatten)]
+        report: InstallReport,
+    },
+    Warning {
+        message: String,
+    },
 }
 
 pub struct IndicatifReporter;
@@ -14,6 +51,19 @@ impl Reporter for IndicatifReporter {
     }
 }
 
+/// Streams each [`Event`] to stdout as a single line of JSON, for consumption by other tools.
+pub struct JsonlReporter;
+
+impl Reporter for JsonlReporter {
+    fn create_progress(&self) -> Box<dyn Progress> {
+        Box::new(VoidProgress)
+    }
+
+    fn event(&self, event: &Event) {
+        println!("{}", serde_json::to_string(event).unwrap());
+    }
+}
+
 pub struct VoidReporter;
 
 impl Reporter for VoidReporter {
@@ -22,6 +72,16 @@ impl Reporter for VoidReporter {
     }
 }
 
+/// Draws no progress bars, for logs which can't render them. Messages printed through a bar, such
+/// as a package finishing installation, are still written to stderr.
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn create_progress(&self) -> Box<dyn Progress> {
+        Box::new(SilentProgress)
+    }
+}
+
 pub trait Progress {
     fn add_bar(&self) -> Box<dyn ProgressBarTrait>;
 }
@@ -40,6 +100,14 @@ impl Progress for VoidProgress {
     }
 }
 
+struct SilentProgress;
+
+impl Progress for SilentProgress {
+    fn add_bar(&self) -> Box<dyn ProgressBarTrait> {
+        Box::new(SilentProgressBar)
+    }
+}
+
 pub trait ProgressBarTrait {
     fn inc(&self, count: u64);
     fn set_length(&self, length: u64);
@@ -98,3 +166,24 @@ impl ProgressBarTrait for VoidProgressBar {
 
     fn finish_with_message(&self, message: String) {}
 }
+
+struct SilentProgressBar;
+
+#[allow(unused)]
+impl ProgressBarTrait for SilentProgressBar {
+    fn inc(&self, count: u64) {}
+
+    fn set_length(&self, length: u64) {}
+
+    fn println(&self, message: &str) {
+        eprintln!("{message}");
+    }
+
+    fn set_message(&self, message: String) {}
+
+    fn finish(&self) {}
+
+    fn finish_and_clear(&self) {}
+
+    fn finish_with_message(&self, message: String) {}
+}
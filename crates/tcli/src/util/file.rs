@@ -0,0 +1,75 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{Error, IoResultToTcli};
+
+/// Replace the contents of the file at the given path without ever leaving it partially written.
+/// The contents are written to a sibling temp file which is then renamed over the original, so
+/// an interrupted write leaves the previous contents intact.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut temp_file = File::create(&temp_path).map_fs_error(&temp_path)?;
+    temp_file
+        .write_all(contents.as_ref())
+        .and_then(|_| temp_file.sync_all())
+        .map_fs_error(&temp_path)?;
+
+    fs::rename(&temp_path, path).map_fs_error(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_creates_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        write_atomic(&path, "contents").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "contents");
+    }
+
+    #[test]
+    fn write_atomic_replaces_longer_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        write_atomic(&path, "much longer original contents").unwrap();
+        write_atomic(&path, "short").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "short");
+    }
+
+    #[test]
+    fn write_atomic_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        write_atomic(&path, "contents").unwrap();
+
+        let names = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|x| x.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["state.json"]);
+    }
+
+    #[test]
+    fn failed_write_atomic_keeps_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        write_atomic(&path, "original").unwrap();
+
+        // A directory in place of the temp file makes the write fail before the rename.
+        fs::create_dir(dir.path().join("state.json.tmp")).unwrap();
+
+        assert!(write_atomic(&path, "replacement").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+    }
+}
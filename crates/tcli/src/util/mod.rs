@@ -1,4 +1,10 @@
+pub mod file;
 pub mod os;
+pub mod path;
+mod secret;
 mod temp_file;
+#[cfg(test)]
+pub mod test_server;
 
+pub use secret::SecretString;
 pub use temp_file::TempFile;
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+/// Windows rejects paths longer than MAX_PATH unless they are written in the extended-length
+/// `\\?\` form, which deep mod trees under long Steam library paths easily exceed. Convert
+/// the path into its extended-length form, so that every path joined onto it is exempt from the
+/// limit however deep it goes. This is a no-op on other platforms.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    // Extended-length paths skip normalization, so they must be absolute and use backslashes.
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+
+    let mut components = absolute.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return absolute;
+    };
+
+    let mut long = OsString::from(r"\\?\");
+    match prefix.kind() {
+        Prefix::Disk(letter) => long.push(format!("{}:", letter as char)),
+        Prefix::UNC(server, share) => {
+            long.push(r"UNC\");
+            long.push(server);
+            long.push(r"\");
+            long.push(share);
+        }
+        // Verbatim and device paths are already exempt from the limit.
+        _ => return absolute,
+    }
+
+    let mut long = PathBuf::from(long);
+    long.extend(components);
+    long
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_disk_paths_are_extended() {
+        assert_eq!(
+            long_path(Path::new(r"C:\Games\profile\project_state")),
+            PathBuf::from(r"\\?\C:\Games\profile\project_state")
+        );
+    }
+
+    #[test]
+    fn unc_paths_are_extended() {
+        assert_eq!(
+            long_path(Path::new(r"\\server\share\profile")),
+            PathBuf::from(r"\\?\UNC\server\share\profile")
+        );
+    }
+
+    #[test]
+    fn verbatim_paths_are_unchanged() {
+        let path = Path::new(r"\\?\C:\Games\profile");
+        assert_eq!(long_path(path), path);
+    }
+}
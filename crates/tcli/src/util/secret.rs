@@ -0,0 +1,69 @@
+use std::fmt::{Debug, Display, Formatter};
+
+/// A string value, such as an auth token, which is redacted when formatted with either `Debug`
+/// or `Display`. The real value can only be read through [`SecretString::expose`].
+#[derive(Clone)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString(value)
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretString([redacted])")
+    }
+}
+
+impl Display for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[redacted]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_redacts_value() {
+        let secret = SecretString::from("tss_0123456789".to_string());
+
+        for formatted in [
+            format!("{secret}"),
+            format!("{secret:?}"),
+            format!("{secret:#?}"),
+        ] {
+            assert!(!formatted.contains("tss_0123456789"), "{formatted}");
+        }
+    }
+
+    #[test]
+    fn containing_struct_redacts_value() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            auth_token: Option<SecretString>,
+        }
+
+        let config = Config {
+            auth_token: Some(SecretString::from("tss_0123456789".to_string())),
+        };
+
+        assert!(!format!("{config:?}").contains("tss_0123456789"));
+    }
+
+    #[test]
+    fn expose_returns_value() {
+        let secret = SecretString::from("tss_0123456789".to_string());
+        assert_eq!(secret.expose(), "tss_0123456789");
+    }
+}